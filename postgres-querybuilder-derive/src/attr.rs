@@ -0,0 +1,147 @@
+use syn::punctuated::Punctuated;
+use syn::token::Comma;
+use syn::{Attribute, Data, DeriveInput, Expr, ExprLit, Field, Fields, Lit, LitStr, Meta, Type};
+
+/// Options given to a field with `#[column(...)]`
+#[derive(Default)]
+pub struct ColumnAttrs {
+    pub rename: Option<String>,
+    pub flatten: bool,
+    pub prefix: Option<String>,
+    pub skip: bool,
+    pub default: bool,
+}
+
+impl ColumnAttrs {
+    pub fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut result = ColumnAttrs::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("column")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let value: LitStr = meta.value()?.parse()?;
+                    result.rename = Some(value.value());
+                } else if meta.path.is_ident("flatten") {
+                    result.flatten = true;
+                } else if meta.path.is_ident("prefix") {
+                    let value: LitStr = meta.value()?.parse()?;
+                    result.prefix = Some(value.value());
+                } else if meta.path.is_ident("skip") {
+                    result.skip = true;
+                } else if meta.path.is_ident("default") {
+                    result.default = true;
+                } else {
+                    return Err(meta.error("unsupported column attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(result)
+    }
+
+    /// Name of the column the field is read from or written to
+    pub fn column(&self, field: &Field) -> String {
+        match self.rename.as_ref() {
+            Some(name) => name.clone(),
+            None => field.ident.as_ref().unwrap().to_string(),
+        }
+    }
+}
+
+/// Fields of a struct with named fields, the only shape the derives support
+pub fn named_fields<'a>(
+    input: &'a DeriveInput,
+    derive: &str,
+) -> syn::Result<&'a Punctuated<Field, Comma>> {
+    match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => Ok(&fields.named),
+            _ => Err(syn::Error::new_spanned(
+                input,
+                format!(
+                    "{} can only be derived for structs with named fields",
+                    derive
+                ),
+            )),
+        },
+        _ => Err(syn::Error::new_spanned(
+            input,
+            format!("{} can only be derived for structs", derive),
+        )),
+    }
+}
+
+/// Table given to the struct with `#[table = "..."]`, or the struct name in snake case
+pub fn table(attrs: &[Attribute], name: &str) -> syn::Result<String> {
+    match attrs.iter().find(|attr| attr.path().is_ident("table")) {
+        Some(attr) => match &attr.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(value),
+                    ..
+                }) => Ok(value.value()),
+                _ => Err(syn::Error::new_spanned(
+                    attr,
+                    "expected #[table = \"name\"]",
+                )),
+            },
+            _ => Err(syn::Error::new_spanned(
+                attr,
+                "expected #[table = \"name\"]",
+            )),
+        },
+        None => Ok(snake_case(name)),
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let mut result = String::new();
+    for (index, character) in name.chars().enumerate() {
+        if character.is_uppercase() {
+            if index > 0 {
+                result.push('_');
+            }
+            result.extend(character.to_lowercase());
+        } else {
+            result.push(character);
+        }
+    }
+    result
+}
+
+/// Whether the type is written as `Option<...>`
+pub fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "Option"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[test]
+    fn table_name() {
+        assert_eq!(table(&[], "User").unwrap(), "user");
+        assert_eq!(table(&[], "BlogPost").unwrap(), "blog_post");
+        let attrs = vec![syn::parse_quote!(#[table = "users"])];
+        assert_eq!(table(&attrs, "User").unwrap(), "users");
+        let attrs = vec![syn::parse_quote!(#[table(users)])];
+        assert!(table(&attrs, "User").is_err());
+    }
+
+    #[test]
+    fn column_attrs() {
+        let attrs = vec![syn::parse_quote!(#[column(skip, default, rename = "full_name")])];
+        let attrs = ColumnAttrs::parse(&attrs).unwrap();
+        assert!(attrs.skip);
+        assert!(attrs.default);
+        assert_eq!(attrs.rename.as_deref(), Some("full_name"));
+        let attrs = vec![syn::parse_quote!(#[column(unknown)])];
+        assert!(ColumnAttrs::parse(&attrs).is_err());
+    }
+}
@@ -0,0 +1,62 @@
+use crate::attr::{is_option, named_fields, table, ColumnAttrs};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::DeriveInput;
+
+pub fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let fields = named_fields(&input, "Changeset")?;
+    let table = table(&input.attrs, &name.to_string())?;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut changes = vec![];
+    for field in fields {
+        let attrs = ColumnAttrs::parse(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+        let ident = field.ident.as_ref().unwrap();
+        let column = attrs.column(field);
+        if !is_option(&field.ty) {
+            if attrs.default {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "#[column(default)] requires an Option field",
+                ));
+            }
+            changes.push(quote! {
+                builder.set(#column, self.#ident.clone());
+            });
+        } else if attrs.default {
+            changes.push(quote! {
+                match self.#ident.as_ref() {
+                    Some(value) => builder.set(#column, value.clone()),
+                    None => builder.set_computed(#column, "DEFAULT"),
+                };
+            });
+        } else {
+            changes.push(quote! {
+                if let Some(value) = self.#ident.as_ref() {
+                    builder.set(#column, value.clone());
+                }
+            });
+        }
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::postgres_querybuilder::model::Changeset for #name #ty_generics #where_clause {
+            fn apply_changes<B: ::postgres_querybuilder::prelude::QueryBuilderWithSet>(
+                &self,
+                builder: &mut B,
+            ) {
+                #(#changes)*
+            }
+
+            fn to_update(&self) -> ::postgres_querybuilder::UpdateBuilder {
+                let mut builder = ::postgres_querybuilder::UpdateBuilder::new(#table);
+                self.apply_changes(&mut builder);
+                builder
+            }
+        }
+    })
+}
@@ -0,0 +1,80 @@
+use crate::attr::{named_fields, table, ColumnAttrs};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::DeriveInput;
+
+pub fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let fields = named_fields(&input, "FromRow")?;
+    let table = table(&input.attrs, &name.to_string())?;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut columns = vec![];
+    let mut values = vec![];
+    for field in fields {
+        let attrs = ColumnAttrs::parse(&field.attrs)?;
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        if attrs.flatten {
+            let prefix = attrs
+                .prefix
+                .clone()
+                .unwrap_or_else(|| format!("{}_", ident));
+            columns.push(quote! {
+                columns.extend(<#ty as ::postgres_querybuilder::model::Model>::columns(
+                    &format!("{}{}", prefix, #prefix),
+                ));
+            });
+            values.push(quote! {
+                #ident: <#ty as ::postgres_querybuilder::model::FromRow>::from_prefixed_row(
+                    row,
+                    &format!("{}{}", prefix, #prefix),
+                )?
+            });
+            continue;
+        }
+        let column = attrs.column(field);
+        columns.push(quote! {
+            columns.push(if prefix.is_empty() {
+                format!("{}.{}", #table, #column)
+            } else {
+                format!("{}.{} AS {}{}", #table, #column, prefix, #column)
+            });
+        });
+        values.push(quote! {
+            #ident: row.try_get(format!("{}{}", prefix, #column).as_str())?
+        });
+    }
+
+    // the trait only exists when the main crate is built with a client
+    let from_row = if cfg!(feature = "from-row") {
+        quote! {
+            impl #impl_generics ::postgres_querybuilder::model::FromRow for #name #ty_generics #where_clause {
+                fn from_prefixed_row(
+                    row: &::postgres_querybuilder::model::Row,
+                    prefix: &str,
+                ) -> Result<Self, ::postgres_querybuilder::model::Error> {
+                    Ok(#name {
+                        #(#values,)*
+                    })
+                }
+            }
+        }
+    } else {
+        TokenStream::new()
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::postgres_querybuilder::model::Model for #name #ty_generics #where_clause {
+            const TABLE: &'static str = #table;
+
+            fn columns(prefix: &str) -> Vec<String> {
+                let mut columns = Vec::new();
+                #(#columns)*
+                columns
+            }
+        }
+
+        #from_row
+    })
+}
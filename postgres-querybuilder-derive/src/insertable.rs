@@ -0,0 +1,65 @@
+use crate::attr::{is_option, named_fields, table, ColumnAttrs};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::DeriveInput;
+
+pub fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let fields = named_fields(&input, "Insertable")?;
+    let table = table(&input.attrs, &name.to_string())?;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut columns = vec![];
+    let mut values = vec![];
+    for field in fields {
+        let attrs = ColumnAttrs::parse(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+        let ident = field.ident.as_ref().unwrap();
+        columns.push(attrs.column(field));
+        if attrs.default {
+            if !is_option(&field.ty) {
+                return Err(syn::Error::new_spanned(
+                    field,
+                    "#[column(default)] requires an Option field",
+                ));
+            }
+            values.push(quote! {
+                match self.#ident.as_ref() {
+                    Some(value) => builder.value(value.clone()),
+                    None => builder.value_default(),
+                };
+            });
+        } else {
+            values.push(quote! {
+                builder.value(self.#ident.clone());
+            });
+        }
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::postgres_querybuilder::model::Insertable for #name #ty_generics #where_clause {
+            fn insert_fields() -> Vec<String> {
+                vec![#(#columns.to_string()),*]
+            }
+
+            fn insert_values<B: ::postgres_querybuilder::prelude::QueryBuilderWithValues>(
+                &self,
+                builder: &mut B,
+            ) {
+                builder.row();
+                #(#values)*
+            }
+
+            fn to_insert(&self) -> ::postgres_querybuilder::InsertBuilder {
+                use ::postgres_querybuilder::prelude::QueryWithFields;
+
+                let mut builder = ::postgres_querybuilder::InsertBuilder::new(#table);
+                builder.fields(Self::insert_fields());
+                self.insert_values(&mut builder);
+                builder
+            }
+        }
+    })
+}
@@ -0,0 +1,66 @@
+//! # Postgres QueryBuilder Derive
+//!
+//! Derive macros for `postgres-querybuilder`, use them through its `derive` feature.
+
+extern crate proc_macro;
+
+mod attr;
+mod changeset;
+mod from_row;
+mod insertable;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+/// Implement `Model` and `FromRow` for a struct with named fields
+///
+/// `FromRow` is only implemented when `postgres-querybuilder` is built with the
+/// `postgres` or `tokio-postgres` feature, `Model` always is.
+///
+/// - `#[table = "users"]` on the struct sets the table, the struct name in snake case by default
+/// - `#[column(rename = "full_name")]` reads the field from another column
+/// - `#[column(flatten)]` reads a nested `FromRow` struct from the same row, with its
+///   columns under the field name as a prefix, like `author_id` for an `author` field,
+///   `#[column(flatten, prefix = "writer_")]` sets another prefix
+///
+/// `Option` fields are `None` when the column is null, a column missing from the row
+/// is an error like for the other fields.
+/// The `skip` and `default` column options only apply to `Insertable` and `Changeset`.
+#[proc_macro_derive(FromRow, attributes(column, table))]
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_row::expand(input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+/// Implement `Insertable` for a struct with named fields
+///
+/// - `#[table = "users"]` on the struct sets the table, the struct name in snake case by default
+/// - `#[column(rename = "full_name")]` writes the field to another column
+/// - `#[column(skip)]` leaves the field out of the insert
+/// - `#[column(default)]` on an `Option` field inserts `DEFAULT` when it is `None`
+#[proc_macro_derive(Insertable, attributes(column, table))]
+pub fn derive_insertable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    insertable::expand(input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
+
+/// Implement `Changeset` for a struct with named fields
+///
+/// `Option` fields are only set when they are `Some`, use `Option<Option<T>>`
+/// to be able to set a column to null.
+///
+/// - `#[table = "users"]` on the struct sets the table, the struct name in snake case by default
+/// - `#[column(rename = "full_name")]` writes the field to another column
+/// - `#[column(skip)]` never sets the field
+/// - `#[column(default)]` on an `Option` field sets the column to `DEFAULT` when it is `None`
+#[proc_macro_derive(Changeset, attributes(column, table))]
+pub fn derive_changeset(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    changeset::expand(input)
+        .unwrap_or_else(|error| error.to_compile_error())
+        .into()
+}
@@ -0,0 +1,222 @@
+use crate::cache::{CacheStats, Lru};
+use crate::prelude::{ExecuteError, QueryBuilder};
+use std::future::Future;
+use std::sync::Mutex;
+use tokio_postgres::{Error, GenericClient, Row, RowStream, Statement};
+
+/// Run builders directly on a `tokio_postgres::Client`, a `tokio_postgres::Transaction`
+/// or any other `tokio_postgres::GenericClient`
+///
+/// Builders are checked with `QueryBuilder::try_build` before anything is sent, an
+/// invalid builder fails with `ExecuteError::Build`.
+///
+/// The futures are `Send`, so generic code can run them in `tokio::spawn`.
+///
+/// Available with the `tokio-postgres` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// use postgres_querybuilder::SelectBuilder;
+/// use postgres_querybuilder::prelude::{AsyncClientExt, QueryBuilderWithWhere};
+/// use tokio_postgres::NoTls;
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let (client, connection) = tokio_postgres::connect("postgres://postgres@localhost/postgres", NoTls).await?;
+/// tokio::spawn(connection);
+/// let mut builder = SelectBuilder::new("users");
+/// builder.where_eq("id", 42);
+/// let user = client.query_opt_builder(&builder).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub trait AsyncClientExt {
+    /// Execute the query and return the number of rows it modified
+    fn execute_builder<Q: QueryBuilder + Sync>(
+        &self,
+        builder: &Q,
+    ) -> impl Future<Output = Result<u64, ExecuteError>> + Send;
+    /// Execute the query and return the rows
+    fn query_builder<Q: QueryBuilder + Sync>(
+        &self,
+        builder: &Q,
+    ) -> impl Future<Output = Result<Vec<Row>, ExecuteError>> + Send;
+    /// Execute the query and return the only row, failing on zero or several rows
+    fn query_one_builder<Q: QueryBuilder + Sync>(
+        &self,
+        builder: &Q,
+    ) -> impl Future<Output = Result<Row, ExecuteError>> + Send;
+    /// Execute the query and return at most one row, failing on several rows
+    fn query_opt_builder<Q: QueryBuilder + Sync>(
+        &self,
+        builder: &Q,
+    ) -> impl Future<Output = Result<Option<Row>, ExecuteError>> + Send;
+    /// Execute the query and stream the rows as they are received
+    fn query_raw_builder<Q: QueryBuilder + Sync>(
+        &self,
+        builder: &Q,
+    ) -> impl Future<Output = Result<RowStream, ExecuteError>> + Send;
+}
+
+impl<C: GenericClient + Sync> AsyncClientExt for C {
+    async fn execute_builder<Q: QueryBuilder + Sync>(
+        &self,
+        builder: &Q,
+    ) -> Result<u64, ExecuteError> {
+        let built = builder.try_build()?;
+        Ok(self.execute(built.query.as_str(), &built.params).await?)
+    }
+
+    async fn query_builder<Q: QueryBuilder + Sync>(
+        &self,
+        builder: &Q,
+    ) -> Result<Vec<Row>, ExecuteError> {
+        let built = builder.try_build()?;
+        Ok(self.query(built.query.as_str(), &built.params).await?)
+    }
+
+    async fn query_one_builder<Q: QueryBuilder + Sync>(
+        &self,
+        builder: &Q,
+    ) -> Result<Row, ExecuteError> {
+        let built = builder.try_build()?;
+        Ok(self.query_one(built.query.as_str(), &built.params).await?)
+    }
+
+    async fn query_opt_builder<Q: QueryBuilder + Sync>(
+        &self,
+        builder: &Q,
+    ) -> Result<Option<Row>, ExecuteError> {
+        let built = builder.try_build()?;
+        Ok(self.query_opt(built.query.as_str(), &built.params).await?)
+    }
+
+    async fn query_raw_builder<Q: QueryBuilder + Sync>(
+        &self,
+        builder: &Q,
+    ) -> Result<RowStream, ExecuteError> {
+        let built = builder.try_build()?;
+        Ok(self.query_raw(built.query.as_str(), built.params).await?)
+    }
+}
+
+/// Asynchronous client wrapper keeping the statements it prepares, keyed by their sql
+///
+/// See `StatementCache` for the blocking version.
+///
+/// # Examples
+///
+/// ```no_run
+/// use postgres_querybuilder::{AsyncStatementCache, SelectBuilder};
+/// use postgres_querybuilder::prelude::{AsyncClientExt, QueryBuilderWithWhere};
+/// use tokio_postgres::NoTls;
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let (client, connection) = tokio_postgres::connect("postgres://postgres@localhost/postgres", NoTls).await?;
+/// tokio::spawn(connection);
+/// let cache = AsyncStatementCache::new(client, 100);
+/// for id in 0..10 {
+///     let mut builder = SelectBuilder::new("users");
+///     builder.where_eq("id", id);
+///     cache.query_opt_builder(&builder).await?;
+/// }
+///
+/// assert_eq!(cache.stats().hits, 9);
+/// # Ok(())
+/// # }
+/// ```
+pub struct AsyncStatementCache<C> {
+    client: C,
+    statements: Mutex<Lru<Statement>>,
+}
+
+impl<C: GenericClient> AsyncStatementCache<C> {
+    pub fn new(client: C, capacity: usize) -> Self {
+        AsyncStatementCache {
+            client,
+            statements: Mutex::new(Lru::new(capacity)),
+        }
+    }
+
+    /// Get the statement prepared for a query, preparing it on a miss
+    pub async fn prepare(&self, query: &str) -> Result<Statement, Error> {
+        let cached = self.statements.lock().unwrap().get(query);
+        if let Some(statement) = cached {
+            return Ok(statement);
+        }
+        let statement = self.client.prepare(query).await?;
+        self.statements
+            .lock()
+            .unwrap()
+            .insert(query.to_string(), statement.clone());
+        Ok(statement)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.statements.lock().unwrap().stats()
+    }
+
+    /// Number of statements currently cached
+    pub fn len(&self) -> usize {
+        self.statements.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get_ref(&self) -> &C {
+        &self.client
+    }
+
+    pub fn into_inner(self) -> C {
+        self.client
+    }
+}
+
+impl<C: GenericClient + Sync> AsyncClientExt for AsyncStatementCache<C> {
+    async fn execute_builder<Q: QueryBuilder + Sync>(
+        &self,
+        builder: &Q,
+    ) -> Result<u64, ExecuteError> {
+        let built = builder.try_build()?;
+        let statement = self.prepare(built.query.as_str()).await?;
+        Ok(self.client.execute(&statement, &built.params).await?)
+    }
+
+    async fn query_builder<Q: QueryBuilder + Sync>(
+        &self,
+        builder: &Q,
+    ) -> Result<Vec<Row>, ExecuteError> {
+        let built = builder.try_build()?;
+        let statement = self.prepare(built.query.as_str()).await?;
+        Ok(self.client.query(&statement, &built.params).await?)
+    }
+
+    async fn query_one_builder<Q: QueryBuilder + Sync>(
+        &self,
+        builder: &Q,
+    ) -> Result<Row, ExecuteError> {
+        let built = builder.try_build()?;
+        let statement = self.prepare(built.query.as_str()).await?;
+        Ok(self.client.query_one(&statement, &built.params).await?)
+    }
+
+    async fn query_opt_builder<Q: QueryBuilder + Sync>(
+        &self,
+        builder: &Q,
+    ) -> Result<Option<Row>, ExecuteError> {
+        let built = builder.try_build()?;
+        let statement = self.prepare(built.query.as_str()).await?;
+        Ok(self.client.query_opt(&statement, &built.params).await?)
+    }
+
+    async fn query_raw_builder<Q: QueryBuilder + Sync>(
+        &self,
+        builder: &Q,
+    ) -> Result<RowStream, ExecuteError> {
+        let built = builder.try_build()?;
+        let statement = self.prepare(built.query.as_str()).await?;
+        Ok(self.client.query_raw(&statement, built.params).await?)
+    }
+}
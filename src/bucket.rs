@@ -1,22 +1,35 @@
+use crate::error::QueryBuildError;
+use crate::ident::{is_strict, Ident};
 use postgres_types::ToSql;
 
 type BucketValue = dyn ToSql + Sync;
 
+/// Maximum number of parameters postgres accepts in a single statement
+pub const MAX_PARAMS: usize = 65535;
+
+#[derive(Default)]
 pub struct Bucket {
     pub content: Vec<Box<BucketValue>>,
+    /// Rendered identifiers that `IdentPolicy::Strict` rejects
+    pub(crate) invalid_idents: Vec<String>,
+    /// Validation errors of the builders embedded as subqueries
+    pub(crate) errors: Vec<QueryBuildError>,
 }
 
 impl Bucket {
     pub fn new() -> Bucket {
-        Bucket { content: vec![] }
+        Bucket {
+            content: vec![],
+            invalid_idents: vec![],
+            errors: vec![],
+        }
     }
 
-    pub fn get_refs(self) -> Vec<&'static BucketValue> {
-        let mut args: Vec<&BucketValue> = vec![];
-        for item in self.content {
-            args.push(Box::leak(item));
-        }
-        args
+    /// Borrow every parameter, in placeholder order, so they can be passed to a client
+    ///
+    /// The values stay owned by the bucket and are freed when it is dropped.
+    pub fn get_refs(&self) -> Vec<&BucketValue> {
+        self.content.iter().map(|item| item.as_ref()).collect()
     }
 
     pub fn push<T: 'static + ToSql + Sync + Clone>(&mut self, value: T) -> usize {
@@ -24,7 +37,164 @@ impl Bucket {
         self.content.len()
     }
 
+    /// Move every parameter of `other` at the end of this bucket
+    ///
+    /// Returns the number of parameters that were already there, which is the offset
+    /// to apply to the placeholders of the query `other` was built for.
+    pub fn append(&mut self, other: Bucket) -> usize {
+        let offset = self.content.len();
+        self.content.extend(other.content);
+        self.invalid_idents.extend(other.invalid_idents);
+        self.errors.extend(other.errors);
+        offset
+    }
+
+    /// Render an identifier, keeping it aside when the strict policy would reject it
+    pub(crate) fn ident(&mut self, ident: impl Into<Ident>) -> String {
+        let ident = ident.into().to_string();
+        self.check_ident(&ident);
+        ident
+    }
+
+    /// Keep an already rendered identifier aside when the strict policy would reject it
+    pub(crate) fn check_ident(&mut self, ident: &str) {
+        if !is_strict(ident) {
+            self.invalid_idents.push(ident.to_string());
+        }
+    }
+
+    /// Report the first validation error of the embedded builders
+    pub(crate) fn check_subqueries(&self) -> Result<(), QueryBuildError> {
+        match self.errors.first() {
+            Some(error) => Err(error.clone()),
+            None => Ok(()),
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.content.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty()
+    }
+}
+
+/// Shift every `$n` placeholder of a query by `offset`
+///
+/// String literals, quoted identifiers and dollar quoted strings are left untouched.
+///
+/// # Examples
+///
+/// ```
+/// use postgres_querybuilder::bucket::shift_placeholders;
+///
+/// assert_eq!(
+///     shift_placeholders("SELECT * FROM users WHERE id = $1 AND name <> '$1'", 2),
+///     "SELECT * FROM users WHERE id = $3 AND name <> '$1'"
+/// );
+/// assert_eq!(shift_placeholders("SELECT $99999999999999999999", 2), "SELECT $99999999999999999999");
+/// ```
+pub fn shift_placeholders(query: &str, offset: usize) -> String {
+    if offset == 0 {
+        return query.to_string();
+    }
+    map_placeholders(query, |index| index + offset)
+}
+
+/// Replace every `$n` placeholder of a query by `$m`, where `m` is given by `mapper(n)`
+///
+/// String literals, quoted identifiers and dollar quoted strings are left untouched.
+///
+/// # Examples
+///
+/// ```
+/// use postgres_querybuilder::bucket::map_placeholders;
+///
+/// let mut found = vec![];
+/// let query = map_placeholders("SELECT $2, $1, $$ $3 $$", |index| {
+///     found.push(index);
+///     index * 10
+/// });
+///
+/// assert_eq!(query, "SELECT $20, $10, $$ $3 $$");
+/// assert_eq!(found, vec![2, 1]);
+/// ```
+pub fn map_placeholders<F: FnMut(usize) -> usize>(query: &str, mut mapper: F) -> String {
+    let chars: Vec<char> = query.chars().collect();
+    let mut result = String::with_capacity(query.len());
+    let mut index = 0;
+    while index < chars.len() {
+        let character = chars[index];
+        match character {
+            '\'' | '"' => {
+                let end = find_char(&chars, index + 1, character);
+                result.extend(&chars[index..end]);
+                index = end;
+            }
+            '$' if is_word_char(index.checked_sub(1).map(|previous| chars[previous])) => {
+                result.push(character);
+                index += 1;
+            }
+            '$' if chars
+                .get(index + 1)
+                .is_some_and(|next| next.is_ascii_digit()) =>
+            {
+                let start = index + 1;
+                let mut end = start;
+                while end < chars.len() && chars[end].is_ascii_digit() {
+                    end += 1;
+                }
+                let number: String = chars[start..end].iter().collect();
+                // a number too large to be a placeholder is left as is
+                match number.parse() {
+                    Ok(number) => result.push_str(&format!("${}", mapper(number))),
+                    Err(_) => result.extend(&chars[index..end]),
+                }
+                index = end;
+            }
+            '$' => {
+                let end = skip_dollar_quoted(&chars, index);
+                result.extend(&chars[index..end]);
+                index = end;
+            }
+            _ => {
+                result.push(character);
+                index += 1;
+            }
+        }
+    }
+    result
+}
+
+pub(crate) fn is_word_char(character: Option<char>) -> bool {
+    character.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Index right after the next occurrence of `quote`, or the end of the query
+pub(crate) fn find_char(chars: &[char], from: usize, quote: char) -> usize {
+    chars[from..]
+        .iter()
+        .position(|c| *c == quote)
+        .map_or(chars.len(), |position| from + position + 1)
+}
+
+/// Index right after a `$tag$ ... $tag$` string starting at `start`
+pub(crate) fn skip_dollar_quoted(chars: &[char], start: usize) -> usize {
+    let mut tag_end = start + 1;
+    while tag_end < chars.len() && (chars[tag_end].is_alphanumeric() || chars[tag_end] == '_') {
+        tag_end += 1;
+    }
+    if tag_end >= chars.len() || chars[tag_end] != '$' {
+        return start + 1;
+    }
+    let tag = &chars[start..=tag_end];
+    let mut index = tag_end + 1;
+    while index + tag.len() <= chars.len() {
+        if &chars[index..index + tag.len()] == tag {
+            return index + tag.len();
+        }
+        index += 1;
+    }
+    chars.len()
 }
@@ -0,0 +1,102 @@
+use std::collections::{BTreeMap, HashMap};
+
+/// Hit and miss counters of a statement cache
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Least recently used map from rendered queries to prepared statements
+pub(crate) struct Lru<V> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<String, (V, u64)>,
+    order: BTreeMap<u64, String>,
+    stats: CacheStats,
+}
+
+impl<V: Clone> Lru<V> {
+    pub fn new(capacity: usize) -> Self {
+        Lru {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Look for a value, marking it as the most recently used and counting a hit or a miss
+    pub fn get(&mut self, key: &str) -> Option<V> {
+        self.tick += 1;
+        match self.entries.get_mut(key) {
+            Some((value, used)) => {
+                self.order.remove(used);
+                *used = self.tick;
+                self.order.insert(self.tick, key.to_string());
+                self.stats.hits += 1;
+                Some(value.clone())
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Store a value, evicting the least recently used ones when the cache is full
+    pub fn insert(&mut self, key: String, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        if let Some((_, used)) = self.entries.remove(&key) {
+            self.order.remove(&used);
+        }
+        while self.entries.len() >= self.capacity {
+            let oldest = *self.order.keys().next().unwrap();
+            let evicted = self.order.remove(&oldest).unwrap();
+            self.entries.remove(&evicted);
+        }
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(key, (value, self.tick));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[test]
+    fn evict_least_recently_used() {
+        let mut cache = Lru::new(2);
+        cache.insert("a".to_string(), 1);
+        cache.insert("b".to_string(), 2);
+        assert_eq!(cache.get("a"), Some(1));
+        cache.insert("c".to_string(), 3);
+        assert_eq!(cache.get("b"), None);
+        assert_eq!(cache.get("a"), Some(1));
+        assert_eq!(cache.get("c"), Some(3));
+        cache.insert("c".to_string(), 4);
+        assert_eq!(cache.get("c"), Some(4));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.stats(), CacheStats { hits: 4, misses: 1 });
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let mut cache = Lru::new(0);
+        cache.insert("a".to_string(), 1);
+        assert_eq!(cache.get("a"), None);
+        assert_eq!(cache.len(), 0);
+    }
+}
@@ -0,0 +1,68 @@
+/// Sections of a statement, declared in the order postgres' grammar expects them
+///
+/// Every statement only uses some of them, but their relative order is the same
+/// for all of them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Clause {
+    With,
+    Target,
+    Columns,
+    Set,
+    Values,
+    From,
+    Join,
+    Where,
+    GroupBy,
+    Having,
+    Window,
+    OrderBy,
+    Limit,
+    Offset,
+    Locking,
+    OnConflict,
+    Returning,
+}
+
+/// Collects the rendered sections of a statement and joins them in grammar order
+pub(crate) struct Sections {
+    items: Vec<(Clause, String)>,
+}
+
+impl Sections {
+    pub fn new() -> Self {
+        Sections { items: vec![] }
+    }
+
+    pub fn push<S: Into<Option<String>>>(&mut self, clause: Clause, section: S) -> &mut Self {
+        if let Some(section) = section.into() {
+            self.items.push((clause, section));
+        }
+        self
+    }
+
+    pub fn render(mut self) -> String {
+        self.items.sort_by_key(|(clause, _)| *clause);
+        let result: Vec<String> = self.items.into_iter().map(|(_, section)| section).collect();
+        result.join(" ")
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[test]
+    fn render_in_grammar_order() {
+        let mut sections = Sections::new();
+        sections.push(Clause::Returning, Some("RETURNING id".to_string()));
+        sections.push(Clause::Where, Some("WHERE id = $1".to_string()));
+        sections.push(Clause::Set, None);
+        sections.push(Clause::Target, "UPDATE users".to_string());
+        sections.push(Clause::Set, Some("SET name = $2".to_string()));
+        sections.push(Clause::With, Some("WITH a AS (SELECT 1)".to_string()));
+        assert_eq!(
+            sections.render(),
+            "WITH a AS (SELECT 1) UPDATE users SET name = $2 WHERE id = $1 RETURNING id"
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use crate::cache::{CacheStats, Lru};
+use crate::prelude::{ExecuteError, QueryBuilder};
+use postgres::{Error, GenericClient, Row, RowIter, Statement};
+
+/// Run builders directly on a `postgres::Client` or `postgres::Transaction`
+///
+/// Builders are checked with `QueryBuilder::try_build` before anything is sent, an
+/// invalid builder fails with `ExecuteError::Build`.
+///
+/// Available with the `postgres` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// use postgres::{Client, NoTls};
+/// use postgres_querybuilder::SelectBuilder;
+/// use postgres_querybuilder::prelude::{ClientExt, QueryBuilderWithWhere};
+///
+/// let mut client = Client::connect("postgres://postgres@localhost/postgres", NoTls).unwrap();
+/// let mut builder = SelectBuilder::new("users");
+/// builder.where_eq("id", 42);
+/// let user = client.query_opt_builder(&builder).unwrap();
+/// ```
+pub trait ClientExt {
+    /// Execute the query and return the number of rows it modified
+    fn execute_builder<Q: QueryBuilder>(&mut self, builder: &Q) -> Result<u64, ExecuteError>;
+    /// Execute the query and return the rows
+    fn query_builder<Q: QueryBuilder>(&mut self, builder: &Q) -> Result<Vec<Row>, ExecuteError>;
+    /// Execute the query and return the only row, failing on zero or several rows
+    fn query_one_builder<Q: QueryBuilder>(&mut self, builder: &Q) -> Result<Row, ExecuteError>;
+    /// Execute the query and return at most one row, failing on several rows
+    fn query_opt_builder<Q: QueryBuilder>(
+        &mut self,
+        builder: &Q,
+    ) -> Result<Option<Row>, ExecuteError>;
+    /// Execute the query and iterate over the rows as they are received
+    fn query_raw_builder<Q: QueryBuilder>(
+        &mut self,
+        builder: &Q,
+    ) -> Result<RowIter<'_>, ExecuteError>;
+}
+
+impl<C: GenericClient> ClientExt for C {
+    fn execute_builder<Q: QueryBuilder>(&mut self, builder: &Q) -> Result<u64, ExecuteError> {
+        let built = builder.try_build()?;
+        Ok(self.execute(built.query.as_str(), &built.params)?)
+    }
+
+    fn query_builder<Q: QueryBuilder>(&mut self, builder: &Q) -> Result<Vec<Row>, ExecuteError> {
+        let built = builder.try_build()?;
+        Ok(self.query(built.query.as_str(), &built.params)?)
+    }
+
+    fn query_one_builder<Q: QueryBuilder>(&mut self, builder: &Q) -> Result<Row, ExecuteError> {
+        let built = builder.try_build()?;
+        Ok(self.query_one(built.query.as_str(), &built.params)?)
+    }
+
+    fn query_opt_builder<Q: QueryBuilder>(
+        &mut self,
+        builder: &Q,
+    ) -> Result<Option<Row>, ExecuteError> {
+        let built = builder.try_build()?;
+        Ok(self.query_opt(built.query.as_str(), &built.params)?)
+    }
+
+    fn query_raw_builder<Q: QueryBuilder>(
+        &mut self,
+        builder: &Q,
+    ) -> Result<RowIter<'_>, ExecuteError> {
+        let built = builder.try_build()?;
+        Ok(self.query_raw(built.query.as_str(), built.params)?)
+    }
+}
+
+/// Client wrapper keeping the statements it prepares, keyed by their sql
+///
+/// Builders render `$n` placeholders instead of values, so the same query shape
+/// with different values reuses the same statement. The least recently used
+/// statements are dropped once `capacity` is reached.
+///
+/// # Examples
+///
+/// ```no_run
+/// use postgres::{Client, NoTls};
+/// use postgres_querybuilder::{SelectBuilder, StatementCache};
+/// use postgres_querybuilder::prelude::{ClientExt, QueryBuilderWithWhere};
+///
+/// let client = Client::connect("postgres://postgres@localhost/postgres", NoTls).unwrap();
+/// let mut cache = StatementCache::new(client, 100);
+/// for id in 0..10 {
+///     let mut builder = SelectBuilder::new("users");
+///     builder.where_eq("id", id);
+///     cache.query_opt_builder(&builder).unwrap();
+/// }
+///
+/// assert_eq!(cache.stats().hits, 9);
+/// ```
+pub struct StatementCache<C> {
+    client: C,
+    statements: Lru<Statement>,
+}
+
+impl<C: GenericClient> StatementCache<C> {
+    pub fn new(client: C, capacity: usize) -> Self {
+        StatementCache {
+            client,
+            statements: Lru::new(capacity),
+        }
+    }
+
+    /// Get the statement prepared for a query, preparing it on a miss
+    pub fn prepare(&mut self, query: &str) -> Result<Statement, Error> {
+        if let Some(statement) = self.statements.get(query) {
+            return Ok(statement);
+        }
+        let statement = self.client.prepare(query)?;
+        self.statements.insert(query.to_string(), statement.clone());
+        Ok(statement)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.statements.stats()
+    }
+
+    /// Number of statements currently cached
+    pub fn len(&self) -> usize {
+        self.statements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.statements.len() == 0
+    }
+
+    pub fn get_mut(&mut self) -> &mut C {
+        &mut self.client
+    }
+
+    pub fn into_inner(self) -> C {
+        self.client
+    }
+}
+
+impl<C: GenericClient> ClientExt for StatementCache<C> {
+    fn execute_builder<Q: QueryBuilder>(&mut self, builder: &Q) -> Result<u64, ExecuteError> {
+        let built = builder.try_build()?;
+        let statement = self.prepare(built.query.as_str())?;
+        Ok(self.client.execute(&statement, &built.params)?)
+    }
+
+    fn query_builder<Q: QueryBuilder>(&mut self, builder: &Q) -> Result<Vec<Row>, ExecuteError> {
+        let built = builder.try_build()?;
+        let statement = self.prepare(built.query.as_str())?;
+        Ok(self.client.query(&statement, &built.params)?)
+    }
+
+    fn query_one_builder<Q: QueryBuilder>(&mut self, builder: &Q) -> Result<Row, ExecuteError> {
+        let built = builder.try_build()?;
+        let statement = self.prepare(built.query.as_str())?;
+        Ok(self.client.query_one(&statement, &built.params)?)
+    }
+
+    fn query_opt_builder<Q: QueryBuilder>(
+        &mut self,
+        builder: &Q,
+    ) -> Result<Option<Row>, ExecuteError> {
+        let built = builder.try_build()?;
+        let statement = self.prepare(built.query.as_str())?;
+        Ok(self.client.query_opt(&statement, &built.params)?)
+    }
+
+    fn query_raw_builder<Q: QueryBuilder>(
+        &mut self,
+        builder: &Q,
+    ) -> Result<RowIter<'_>, ExecuteError> {
+        let built = builder.try_build()?;
+        let statement = self.prepare(built.query.as_str())?;
+        Ok(self.client.query_raw(&statement, built.params)?)
+    }
+}
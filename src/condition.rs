@@ -0,0 +1,245 @@
+use crate::bucket::{shift_placeholders, Bucket};
+use crate::fragment::Fragment;
+use crate::ident::Ident;
+use postgres_types::ToSql;
+use std::ops::Not;
+
+/// Boolean expression that can be combined with `and`, `or` and `!`
+///
+/// Each condition carries its own parameters, they get merged into the builder's
+/// bucket and renumbered when the condition is added with `where_expr`.
+pub enum Condition {
+    /// Atomic predicate with its own `$n` placeholders starting at `$1`
+    Expr(String, Bucket),
+    /// Raw sql, wrapped in parentheses when combined with other conditions
+    Raw(String),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+    Not(Box<Condition>),
+    /// Sql fragment, wrapped in parentheses when combined with other conditions
+    Fragment(Fragment),
+}
+
+impl Condition {
+    /// Create a condition from raw sql
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::prelude::Condition;
+    ///
+    /// let (query, _) = Condition::raw("deleted_at IS NULL OR archived").build();
+    ///
+    /// assert_eq!(query, "(deleted_at IS NULL OR archived)");
+    /// ```
+    pub fn raw(sql: &str) -> Self {
+        Condition::Raw(sql.to_string())
+    }
+
+    /// Create a `field = value` condition
+    pub fn eq<T: 'static + ToSql + Sync + Clone>(field: impl Into<Ident>, value: T) -> Self {
+        Self::compare(field.into(), "=", value)
+    }
+
+    /// Create a `field <> value` condition
+    pub fn ne<T: 'static + ToSql + Sync + Clone>(field: impl Into<Ident>, value: T) -> Self {
+        Self::compare(field.into(), "<>", value)
+    }
+
+    fn compare<T: 'static + ToSql + Sync + Clone>(field: Ident, operator: &str, value: T) -> Self {
+        let mut params = Bucket::new();
+        let field = params.ident(field);
+        let index = params.push(value);
+        Condition::Expr(format!("{} {} ${}", field, operator, index), params)
+    }
+
+    /// Combine with another condition, both must be true
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::prelude::Condition;
+    ///
+    /// let condition = Condition::eq("role", "admin")
+    ///     .or(Condition::eq("role", "owner"))
+    ///     .and(!Condition::eq("banned", true));
+    /// let (query, params) = condition.build();
+    ///
+    /// assert_eq!(query, "(role = $1 OR role = $2) AND NOT (banned = $3)");
+    /// assert_eq!(params.len(), 3);
+    /// ```
+    pub fn and(self, other: Condition) -> Self {
+        match self {
+            Condition::And(mut items) => {
+                items.push(other);
+                Condition::And(items)
+            }
+            _ => Condition::And(vec![self, other]),
+        }
+    }
+
+    /// Combine with another condition, at least one must be true
+    pub fn or(self, other: Condition) -> Self {
+        match self {
+            Condition::Or(mut items) => {
+                items.push(other);
+                Condition::Or(items)
+            }
+            _ => Condition::Or(vec![self, other]),
+        }
+    }
+
+    /// Render the condition, with its placeholders starting at `$1`, and its parameters
+    ///
+    /// The output can safely be joined with other conditions using ` AND `.
+    pub fn build(self) -> (String, Bucket) {
+        self.build_after(0)
+    }
+
+    /// Render the condition for a query that already has `offset` parameters,
+    /// only the placeholders of `Expr` and `Fragment` leaves are shifted
+    pub(crate) fn build_after(self, offset: usize) -> (String, Bucket) {
+        let mut params = Bucket::new();
+        let nested = self.needs_parentheses();
+        let query = self.render(&mut params, offset, nested);
+        (query, params)
+    }
+
+    /// Whether the condition has to be wrapped to be joined with ` AND `
+    fn needs_parentheses(&self) -> bool {
+        match self {
+            Condition::And(items) | Condition::Or(items) if items.len() == 1 => {
+                items[0].needs_parentheses()
+            }
+            Condition::Or(items) => !items.is_empty(),
+            Condition::Raw(_) | Condition::Fragment(_) => true,
+            Condition::Expr(..) | Condition::And(_) | Condition::Not(_) => false,
+        }
+    }
+
+    fn render(self, params: &mut Bucket, offset: usize, nested: bool) -> String {
+        match self {
+            Condition::Expr(sql, values) => {
+                let shift = offset + params.append(values);
+                shift_placeholders(&sql, shift)
+            }
+            Condition::Raw(sql) if nested => format!("({})", sql),
+            Condition::Raw(sql) => sql,
+            Condition::And(items) => {
+                Self::render_list(items, " AND ", "TRUE", params, offset, nested)
+            }
+            Condition::Or(items) => {
+                Self::render_list(items, " OR ", "FALSE", params, offset, nested)
+            }
+            Condition::Not(inner) => format!("NOT ({})", inner.render(params, offset, false)),
+            Condition::Fragment(fragment) => {
+                let (sql, values) = fragment.build();
+                let sql = shift_placeholders(&sql, offset + params.append(values));
+                if nested {
+                    format!("({})", sql)
+                } else {
+                    sql
+                }
+            }
+        }
+    }
+
+    fn render_list(
+        mut items: Vec<Condition>,
+        separator: &str,
+        empty: &str,
+        params: &mut Bucket,
+        offset: usize,
+        nested: bool,
+    ) -> String {
+        match items.len() {
+            0 => empty.to_string(),
+            1 => items.remove(0).render(params, offset, nested),
+            _ => {
+                let result: Vec<String> = items
+                    .into_iter()
+                    .map(|item| item.render(params, offset, true))
+                    .collect();
+                let result = result.join(separator);
+                if nested {
+                    format!("({})", result)
+                } else {
+                    result
+                }
+            }
+        }
+    }
+}
+
+impl From<Fragment> for Condition {
+    fn from(fragment: Fragment) -> Self {
+        Condition::Fragment(fragment)
+    }
+}
+
+impl Not for Condition {
+    type Output = Condition;
+
+    fn not(self) -> Self::Output {
+        Condition::Not(Box::new(self))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::sql;
+
+    #[test]
+    fn nested_groups() {
+        let condition = Condition::eq("a", 1)
+            .and(Condition::eq("b", 2).or(Condition::raw("c IS NULL").and(Condition::ne("d", 3))))
+            .and(!Condition::eq("e", 4).or(Condition::eq("f", 5)));
+        let (query, params) = condition.build();
+        assert_eq!(
+            query,
+            "a = $1 AND (b = $2 OR ((c IS NULL) AND d <> $3)) AND NOT (e = $4 OR f = $5)"
+        );
+        assert_eq!(params.len(), 5);
+    }
+
+    #[test]
+    fn top_level_or() {
+        let (query, _) = Condition::eq("a", 1).or(Condition::eq("b", 2)).build();
+        assert_eq!(query, "(a = $1 OR b = $2)");
+    }
+
+    #[test]
+    fn fragments() {
+        let condition = Condition::eq("a", 1)
+            .and(Condition::from(sql!("b = ? OR c = ?", 2, 3).unwrap()))
+            .and(!Condition::from(sql!("d = ?", 4).unwrap()));
+        let (query, params) = condition.build();
+        assert_eq!(query, "a = $1 AND (b = $2 OR c = $3) AND NOT (d = $4)");
+        assert_eq!(params.len(), 4);
+    }
+
+    #[test]
+    fn top_level_raw_and_fragment() {
+        let (query, _) = Condition::raw("a = 1 OR b = 2").build();
+        assert_eq!(query, "(a = 1 OR b = 2)");
+        let (query, _) = Condition::And(vec![Condition::raw("a = 1 OR b = 2")]).build();
+        assert_eq!(query, "(a = 1 OR b = 2)");
+        let (query, _) = Condition::from(sql!("a = ? OR b = ?", 1, 2).unwrap()).build();
+        assert_eq!(query, "(a = $1 OR b = $2)");
+    }
+
+    #[test]
+    fn build_after_keeps_raw_placeholders() {
+        let condition = Condition::raw("b = $2").or(Condition::eq("c", 3));
+        let (query, params) = condition.build_after(2);
+        assert_eq!(query, "((b = $2) OR c = $3)");
+        assert_eq!(params.len(), 1);
+    }
+
+    #[test]
+    fn empty_lists() {
+        assert_eq!(Condition::And(vec![]).build().0, "TRUE");
+        assert_eq!(Condition::Or(vec![]).build().0, "FALSE");
+    }
+}
@@ -1,11 +1,19 @@
-use crate::prelude::*;
 use crate::bucket::Bucket;
+use crate::clause::{Clause, Sections};
+use crate::prelude::*;
 use postgres_types::ToSql;
 
 pub struct DeleteBuilder {
+    with_queries: Vec<String>,
+    recursive: bool,
     table: String,
+    only: bool,
+    alias: Option<String>,
+    using_items: Vec<String>,
     conditions: Vec<String>,
+    returning_fields: Vec<String>,
     params: Bucket,
+    config: BuilderConfig,
 }
 
 impl DeleteBuilder {
@@ -22,24 +30,109 @@ impl DeleteBuilder {
     ///
     /// assert_eq!(builder.get_query(), "DELETE FROM users WHERE id = $1")
     /// ```
-    pub fn new(from: &str) -> DeleteBuilder {
+    pub fn new(from: impl Into<Ident>) -> DeleteBuilder {
+        DeleteBuilder::with_config(from, BuilderConfig::default())
+    }
+
+    /// Create a new delete builder for a given table with the given settings
+    pub fn with_config(from: impl Into<Ident>, config: BuilderConfig) -> DeleteBuilder {
+        let mut params = Bucket::new();
         DeleteBuilder {
-            table: from.to_string(),
+            with_queries: vec![],
+            recursive: false,
+            table: params.ident(from),
+            only: false,
+            alias: None,
+            using_items: vec![],
             conditions: vec![],
-            params: Bucket::new(),
+            returning_fields: vec![],
+            params,
+            config,
         }
     }
+
+    /// Only delete from the table itself, not from the tables inheriting from it
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::DeleteBuilder;
+    /// use postgres_querybuilder::prelude::*;
+    ///
+    /// let mut builder = DeleteBuilder::new("sessions");
+    /// builder.only().alias("s");
+    /// builder.from("users AS u");
+    /// builder.where_condition("u.id = s.user_id");
+    /// builder.where_eq("u.banned", true);
+    /// builder.returning(vec!["s.id"]);
+    ///
+    /// assert_eq!(builder.get_query(), "DELETE FROM ONLY sessions AS s USING users AS u WHERE u.id = s.user_id AND u.banned = $1 RETURNING s.id")
+    /// ```
+    pub fn only(&mut self) -> &mut Self {
+        self.only = true;
+        self
+    }
+
+    /// Give the target table an alias to reference it in the conditions
+    pub fn alias(&mut self, alias: impl Into<Ident>) -> &mut Self {
+        self.alias = Some(self.params.ident(alias));
+        self
+    }
+
+    pub fn get_values(&mut self) -> &Vec<Box<dyn ToSql + Sync + 'static>> {
+        &self.params.content
+    }
 }
 
 impl DeleteBuilder {
+    fn with_queries_to_query(&self) -> Option<String> {
+        if !self.with_queries.is_empty() {
+            let keyword = if self.recursive {
+                "WITH RECURSIVE"
+            } else {
+                "WITH"
+            };
+            Some(format!("{} {}", keyword, self.with_queries.join(", ")))
+        } else {
+            None
+        }
+    }
+
     fn table_to_query(&self) -> String {
-        format!("DELETE FROM {}", self.table)
+        let mut result = String::from("DELETE FROM ");
+        if self.only {
+            result.push_str("ONLY ");
+        }
+        result.push_str(&self.table);
+        if let Some(alias) = self.alias.as_ref() {
+            result.push_str(&format!(" AS {}", alias));
+        }
+        result
+    }
+
+    fn using_to_query(&self) -> Option<String> {
+        if !self.using_items.is_empty() {
+            Some(format!("USING {}", self.using_items.join(", ")))
+        } else {
+            None
+        }
+    }
+
+    fn returning_fields_to_query(&self) -> Option<String> {
+        if !self.returning_fields.is_empty() {
+            let returning_query = self.returning_fields.join(", ");
+            Some(format!("RETURNING {}", returning_query))
+        } else {
+            None
+        }
     }
 
     fn where_to_query(&self) -> Option<String> {
-        if self.conditions.len() > 0 {
+        if !self.conditions.is_empty() {
             let where_query = self.conditions.join(" AND ");
             Some(format!("WHERE {}", where_query))
+        } else if self.config.full_table == FullTablePolicy::Deny {
+            Some("WHERE false".to_string())
         } else {
             None
         }
@@ -51,20 +144,47 @@ impl QueryBuilder for DeleteBuilder {
         self.params.push(value)
     }
 
-    fn get_query(&self) -> String {
-        let mut result: Vec<String> = vec![];
-        result.push(self.table_to_query());
-        match self.where_to_query() {
-            Some(value) => result.push(value),
-            None => (),
-        };
+    fn append_params(&mut self, params: Bucket) -> usize {
+        self.params.append(params)
+    }
+
+    fn params_len(&self) -> usize {
+        self.params.len()
+    }
 
-        result.join(" ")
+    fn get_query(&self) -> String {
+        let mut sections = Sections::new();
+        sections.push(Clause::With, self.with_queries_to_query());
+        sections.push(Clause::Target, self.table_to_query());
+        sections.push(Clause::From, self.using_to_query());
+        sections.push(Clause::Where, self.where_to_query());
+        sections.push(Clause::Returning, self.returning_fields_to_query());
+        sections.render()
     }
 
-    fn get_ref_params(self) -> Vec<&'static (dyn ToSql + Sync)> {
+    fn get_ref_params(&self) -> Vec<&(dyn ToSql + Sync)> {
         self.params.get_refs()
     }
+
+    fn into_params(self) -> Bucket {
+        self.params
+    }
+
+    fn validate(&self) -> Result<(), QueryBuildError> {
+        self.config.check_idents(&self.params)?;
+        self.params.check_subqueries()?;
+        if self.conditions.is_empty() && self.config.full_table == FullTablePolicy::Deny {
+            return Err(QueryBuildError::MissingWhere);
+        }
+        Ok(())
+    }
+}
+
+impl QueryBuilderWithFullTableGuard for DeleteBuilder {
+    fn full_table_policy(&mut self, policy: FullTablePolicy) -> &mut Self {
+        self.config.full_table = policy;
+        self
+    }
 }
 
 impl QueryBuilderWithWhere for DeleteBuilder {
@@ -74,6 +194,37 @@ impl QueryBuilderWithWhere for DeleteBuilder {
     }
 }
 
+impl QueryBuilderWithFrom for DeleteBuilder {
+    /// Add an item to the `USING` list
+    fn from_raw(&mut self, item: &str) -> &mut Self {
+        self.using_items.push(item.to_string());
+        self
+    }
+}
+
+impl QueryBuilderWithReturningColumns for DeleteBuilder {
+    fn returning<I: Into<Ident>>(&mut self, fields: Vec<I>) -> &mut Self {
+        for field in fields {
+            let field = self.params.ident(field);
+            self.returning_fields.push(field);
+        }
+        self
+    }
+}
+
+impl QueryBuilderWithQueries for DeleteBuilder {
+    fn with(&mut self, query: WithQuery) -> &mut Self {
+        let item = query.render(&mut self.params);
+        self.with_queries.push(item);
+        self
+    }
+
+    fn with_recursive(&mut self) -> &mut Self {
+        self.recursive = true;
+        self
+    }
+}
+
 #[cfg(test)]
 pub mod test {
     use super::*;
@@ -88,9 +239,96 @@ pub mod test {
     fn with_where() {
         let mut builder = DeleteBuilder::new("publishers");
         builder.where_eq("id", 22);
+        assert_eq!(builder.get_query(), "DELETE FROM publishers WHERE id = $1",);
+    }
+
+    #[test]
+    fn clause_combinations() {
+        for mask in 0..16 {
+            let enabled = |index: usize| mask & (1 << index) != 0;
+            let mut builder = DeleteBuilder::new("publishers");
+            let mut expected = vec![];
+            if enabled(0) {
+                builder.returning(vec!["id"]);
+            }
+            if enabled(1) {
+                builder.where_condition("publishers.id = tmp.id");
+            }
+            if enabled(2) {
+                builder.from("tmp");
+            }
+            if enabled(3) {
+                builder.with_query("tmp", "SELECT 1 AS id");
+                expected.push("WITH tmp AS (SELECT 1 AS id)");
+            }
+            expected.push("DELETE FROM publishers");
+            if enabled(2) {
+                expected.push("USING tmp");
+            }
+            if enabled(1) {
+                expected.push("WHERE publishers.id = tmp.id");
+            }
+            if enabled(0) {
+                expected.push("RETURNING id");
+            }
+            assert_eq!(builder.get_query(), expected.join(" "));
+        }
+    }
+
+    #[test]
+    fn full_table_guard() {
+        let mut builder = DeleteBuilder::new("users");
+        builder.full_table_policy(FullTablePolicy::Deny);
+        builder.from("banned");
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::MissingWhere)
+        );
+        assert_eq!(
+            builder.get_query(),
+            "DELETE FROM users USING banned WHERE false"
+        );
+        builder.allow_full_table();
+        assert_eq!(
+            builder.try_build().unwrap().query,
+            "DELETE FROM users USING banned"
+        );
+        builder.full_table_policy(FullTablePolicy::Deny);
+        builder.where_condition("banned.user_id = users.id");
+        assert!(builder.try_build().is_ok());
+    }
+
+    #[test]
+    fn with_only_and_alias() {
+        let mut builder = DeleteBuilder::new("publishers");
+        builder.only();
+        assert_eq!(builder.get_query(), "DELETE FROM ONLY publishers");
+        builder.alias("p");
+        assert_eq!(builder.get_query(), "DELETE FROM ONLY publishers AS p");
+    }
+
+    #[test]
+    fn with_where_expr() {
+        let mut builder = DeleteBuilder::new("publishers");
+        builder.where_eq("id", 22);
+        builder.where_expr(Condition::eq("name", "rick").or(Condition::raw("name IS NULL")));
+        assert_eq!(
+            builder.get_query(),
+            "DELETE FROM publishers WHERE id = $1 AND (name = $2 OR (name IS NULL))",
+        );
+    }
+
+    #[test]
+    fn with_where_expr_raw_placeholders() {
+        let mut builder = DeleteBuilder::new("publishers");
+        builder.where_eq("a", 1);
+        let index = builder.add_param(2);
+        builder.where_expr(Condition::raw(&format!("b = ${}", index)).or(Condition::eq("c", 3)));
+        builder.where_expr(Condition::raw("d = 1 OR e = 2"));
         assert_eq!(
             builder.get_query(),
-            "DELETE FROM publishers WHERE id = $1",
+            "DELETE FROM publishers WHERE a = $1 AND ((b = $2) OR c = $3) AND (d = 1 OR e = 2)",
         );
+        assert_eq!(builder.get_ref_params().len(), 3);
     }
 }
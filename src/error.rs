@@ -0,0 +1,185 @@
+use crate::bucket::MAX_PARAMS;
+#[cfg(all(feature = "postgres", not(feature = "tokio-postgres")))]
+use postgres::Error as PostgresError;
+use std::error::Error;
+use std::fmt;
+#[cfg(feature = "tokio-postgres")]
+use tokio_postgres::Error as PostgresError;
+
+/// Structural error found by `QueryBuilder::try_build` before rendering a query
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum QueryBuildError {
+    /// Insert without values, select query or default values
+    MissingValues,
+    /// Row of values that doesn't have one value per field, rows are counted from 0
+    ValuesCountMismatch {
+        row: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// Insert with more than one of values, select query and default values
+    ConflictingValues,
+    /// Insert with default values that also lists its fields
+    DefaultValuesWithFields,
+    /// Update without any field to set
+    MissingSet,
+    /// Update or delete without conditions while the full table policy denies it
+    MissingWhere,
+    /// `ON CONFLICT DO UPDATE` or conflict target predicate without conflict target
+    MissingConflictTarget,
+    /// Conflict target predicate on a `ON CONSTRAINT` target, which postgres doesn't accept
+    ConflictWhereOnConstraint,
+    /// `ON CONFLICT DO UPDATE` on an insert that doesn't list its fields
+    ConflictUpdateWithoutColumns,
+    /// `DISTINCT ON` expressions that don't lead the `ORDER BY` list
+    DistinctOnOrderMismatch,
+    /// Select with both `DISTINCT` and `DISTINCT ON`
+    ConflictingDistinct,
+    /// `ORDER BY` expression of a `DISTINCT` select that isn't a selected column
+    DistinctOrderNotSelected { expression: String },
+    /// Window based on a named window that sets its own `PARTITION BY`
+    WindowPartitionOverride { name: String },
+    /// Window adding an `ORDER BY` to a named window that has one
+    WindowOrderOverride { name: String },
+    /// Window copying a named window that has a frame clause
+    WindowFrameCopy { name: String },
+    /// Locking option like `skip_locked` set before any locking clause
+    MissingLock,
+    /// Row locking clause on a query with `DISTINCT`, `GROUP BY`, `HAVING`, `WINDOW`
+    /// or window functions
+    LockingNotAllowed,
+    /// More parameters than postgres accepts in a single statement
+    TooManyParams { found: usize },
+    /// Placeholder of the raw sql without a bound parameter
+    UnboundPlaceholder { index: usize },
+    /// Raw identifier rejected by `IdentPolicy::Strict`
+    InvalidIdent { name: String },
+}
+
+impl fmt::Display for QueryBuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            QueryBuildError::MissingValues => write!(f, "insert query has no values"),
+            QueryBuildError::ValuesCountMismatch {
+                row,
+                expected,
+                found,
+            } => write!(
+                f,
+                "row {} has {} values but {} were expected",
+                row, found, expected
+            ),
+            QueryBuildError::ConflictingValues => write!(
+                f,
+                "insert query can only use one of values, a select query or default values"
+            ),
+            QueryBuildError::DefaultValuesWithFields => {
+                write!(f, "insert query with default values can't list fields")
+            }
+            QueryBuildError::MissingSet => write!(f, "update query has no field to set"),
+            QueryBuildError::MissingWhere => write!(
+                f,
+                "query has no where condition, call allow_full_table to affect every row"
+            ),
+            QueryBuildError::MissingConflictTarget => {
+                write!(f, "on conflict clause requires a conflict target")
+            }
+            QueryBuildError::ConflictWhereOnConstraint => write!(
+                f,
+                "on conflict where requires a column conflict target, not a constraint"
+            ),
+            QueryBuildError::ConflictUpdateWithoutColumns => {
+                write!(f, "on conflict do update requires the inserted fields")
+            }
+            QueryBuildError::DistinctOnOrderMismatch => write!(
+                f,
+                "distinct on expressions must match the leading order by expressions"
+            ),
+            QueryBuildError::ConflictingDistinct => {
+                write!(f, "select can't be both distinct and distinct on")
+            }
+            QueryBuildError::DistinctOrderNotSelected { expression } => write!(
+                f,
+                "order by expression {:?} of a distinct select must be selected",
+                expression
+            ),
+            QueryBuildError::WindowPartitionOverride { name } => {
+                write!(f, "can't override the partition by of window {:?}", name)
+            }
+            QueryBuildError::MissingLock => {
+                write!(f, "locking option set without a locking clause")
+            }
+            QueryBuildError::WindowOrderOverride { name } => {
+                write!(f, "can't override the order by of window {:?}", name)
+            }
+            QueryBuildError::WindowFrameCopy { name } => write!(
+                f,
+                "can't copy window {:?} because it has a frame clause",
+                name
+            ),
+            QueryBuildError::LockingNotAllowed => write!(
+                f,
+                "row locking is not allowed with distinct, group by, having or window functions"
+            ),
+            QueryBuildError::TooManyParams { found } => write!(
+                f,
+                "query has {} parameters but postgres accepts at most {}",
+                found, MAX_PARAMS
+            ),
+            QueryBuildError::UnboundPlaceholder { index } => {
+                write!(f, "placeholder ${} has no bound parameter", index)
+            }
+            QueryBuildError::InvalidIdent { name } => {
+                write!(f, "invalid unquoted identifier {:?}", name)
+            }
+        }
+    }
+}
+
+impl Error for QueryBuildError {}
+
+/// Error returned when running a builder on a client, either found by `try_build`
+/// before sending the query or returned by postgres
+///
+/// Available with the `postgres` or `tokio-postgres` feature.
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+#[derive(Debug)]
+pub enum ExecuteError {
+    Build(QueryBuildError),
+    Postgres(PostgresError),
+}
+
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+impl fmt::Display for ExecuteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExecuteError::Build(error) => write!(f, "invalid query: {}", error),
+            ExecuteError::Postgres(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+impl Error for ExecuteError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ExecuteError::Build(error) => Some(error),
+            ExecuteError::Postgres(error) => Some(error),
+        }
+    }
+}
+
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+impl From<QueryBuildError> for ExecuteError {
+    fn from(error: QueryBuildError) -> Self {
+        ExecuteError::Build(error)
+    }
+}
+
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+impl From<PostgresError> for ExecuteError {
+    fn from(error: PostgresError) -> Self {
+        ExecuteError::Postgres(error)
+    }
+}
@@ -0,0 +1,213 @@
+use crate::bucket::{find_char, is_word_char, skip_dollar_quoted, Bucket};
+use std::error::Error;
+use std::fmt;
+
+/// Piece of sql with `?` placeholders and the values bound to them
+///
+/// The placeholders are numbered from `$1` and renumbered when the fragment is added
+/// to a builder. Use `??` for a literal question mark, like the jsonb `?` operator.
+/// Question marks inside string literals, quoted identifiers and dollar quoted
+/// strings are left untouched. Numbered `$n` placeholders are rejected, they would
+/// reference the parameters of the builder instead of the fragment's arguments.
+///
+/// # Examples
+///
+/// ```
+/// use postgres_querybuilder::sql;
+///
+/// let (query, params) = sql!("tags ?? ? AND name <> '?' AND age > ?", "admin", 18).unwrap().build();
+///
+/// assert_eq!(query, "tags ? $1 AND name <> '?' AND age > $2");
+/// assert_eq!(params.len(), 2);
+/// ```
+pub struct Fragment {
+    sql: String,
+    params: Bucket,
+}
+
+impl Fragment {
+    /// Create a fragment, the number of `?` placeholders must match the number of arguments
+    /// and numbered `$n` placeholders aren't allowed
+    ///
+    /// The `sql!` macro builds the bucket of arguments from a list of values.
+    pub fn new(sql: &str, args: Bucket) -> Result<Self, FragmentError> {
+        let chars: Vec<char> = sql.chars().collect();
+        let mut result = String::with_capacity(sql.len());
+        let mut placeholders = 0;
+        let mut index = 0;
+        while index < chars.len() {
+            let character = chars[index];
+            match character {
+                '\'' | '"' => {
+                    let end = find_char(&chars, index + 1, character);
+                    result.extend(&chars[index..end]);
+                    index = end;
+                }
+                '$' if is_word_char(index.checked_sub(1).map(|previous| chars[previous])) => {
+                    result.push(character);
+                    index += 1;
+                }
+                '$' if chars
+                    .get(index + 1)
+                    .is_some_and(|next| next.is_ascii_digit()) =>
+                {
+                    let digits = chars[index + 1..]
+                        .iter()
+                        .take_while(|next| next.is_ascii_digit())
+                        .count();
+                    return Err(FragmentError::NumberedPlaceholder {
+                        placeholder: chars[index..index + 1 + digits].iter().collect(),
+                    });
+                }
+                '$' => {
+                    let end = skip_dollar_quoted(&chars, index);
+                    result.extend(&chars[index..end]);
+                    index = end;
+                }
+                '?' if chars.get(index + 1) == Some(&'?') => {
+                    result.push('?');
+                    index += 2;
+                }
+                '?' => {
+                    placeholders += 1;
+                    result.push_str(&format!("${}", placeholders));
+                    index += 1;
+                }
+                _ => {
+                    result.push(character);
+                    index += 1;
+                }
+            }
+        }
+        if placeholders != args.len() {
+            return Err(FragmentError::ArgumentCount {
+                placeholders,
+                arguments: args.len(),
+            });
+        }
+        Ok(Fragment {
+            sql: result,
+            params: args,
+        })
+    }
+
+    /// Render the fragment, with its placeholders starting at `$1`, and its parameters
+    pub fn build(self) -> (String, Bucket) {
+        (self.sql, self.params)
+    }
+}
+
+/// Error returned when the sql of a fragment doesn't match its arguments
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FragmentError {
+    /// The number of arguments doesn't match the number of `?` placeholders
+    ArgumentCount {
+        placeholders: usize,
+        arguments: usize,
+    },
+    /// The sql contains a numbered placeholder like `$1`, use `?` instead
+    NumberedPlaceholder { placeholder: String },
+}
+
+impl fmt::Display for FragmentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FragmentError::ArgumentCount {
+                placeholders,
+                arguments,
+            } => write!(
+                f,
+                "fragment has {} placeholders but {} arguments were given",
+                placeholders, arguments
+            ),
+            FragmentError::NumberedPlaceholder { placeholder } => write!(
+                f,
+                "fragment contains the numbered placeholder {}, use ? instead",
+                placeholder
+            ),
+        }
+    }
+}
+
+impl Error for FragmentError {}
+
+/// Create a `Fragment` from sql with `?` placeholders and values of any type
+///
+/// Returns a `Result`, failing when the number of values doesn't match the placeholders
+/// or when the sql contains a `$n` placeholder.
+///
+/// # Examples
+///
+/// ```
+/// use postgres_querybuilder::{sql, SelectBuilder};
+/// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithWhere};
+///
+/// let mut builder = SelectBuilder::new("users");
+/// builder.where_eq("active", true);
+/// builder.where_fragment(sql!("age BETWEEN ? AND ?", 18, 99).unwrap());
+/// builder.where_fragment(sql!("lower(name) = lower(?)", "Rick").unwrap());
+///
+/// assert_eq!(builder.get_query(), "SELECT * FROM users WHERE active = $1 AND age BETWEEN $2 AND $3 AND lower(name) = lower($4)");
+/// assert!(sql!("age BETWEEN ? AND ?", 18).is_err());
+/// ```
+#[macro_export]
+macro_rules! sql {
+    ($sql:expr $(, $arg:expr)* $(,)?) => {{
+        #[allow(unused_mut)]
+        let mut args = $crate::bucket::Bucket::new();
+        $(args.push($arg);)*
+        $crate::prelude::Fragment::new($sql, args)
+    }};
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[test]
+    fn placeholders() {
+        let (query, params) = sql!("a = ? AND b ?? 'x' AND c = ?", 1, "two".to_string())
+            .unwrap()
+            .build();
+        assert_eq!(query, "a = $1 AND b ? 'x' AND c = $2");
+        assert_eq!(params.len(), 2);
+    }
+
+    #[test]
+    fn quoted_question_marks() {
+        let (query, params) = sql!(r#"a = '?''?' AND "b?" = $$?$$ AND c$d = ?"#, 1)
+            .unwrap()
+            .build();
+        assert_eq!(query, r#"a = '?''?' AND "b?" = $$?$$ AND c$d = $1"#);
+        assert_eq!(params.len(), 1);
+        assert_eq!(sql!("now()").unwrap().build().0, "now()");
+    }
+
+    #[test]
+    fn argument_count_mismatch() {
+        let error = sql!("a = ? AND b = ?", 1).err().unwrap();
+        assert_eq!(
+            error,
+            FragmentError::ArgumentCount {
+                placeholders: 2,
+                arguments: 1
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "fragment has 2 placeholders but 1 arguments were given"
+        );
+        assert!(sql!("a = ?", 1, 2).is_err());
+    }
+
+    #[test]
+    fn numbered_placeholder() {
+        assert_eq!(
+            sql!("a = $12 AND b = ?", 1).err(),
+            Some(FragmentError::NumberedPlaceholder {
+                placeholder: "$12".to_string()
+            })
+        );
+        assert!(sql!("a = '$1' AND b = $$ $1 $$ AND c$1 = ?", 1).is_ok());
+    }
+}
@@ -0,0 +1,266 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+    Raw(String),
+    Quoted(String),
+}
+
+impl fmt::Display for Part {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Part::Raw(name) => write!(f, "{}", name),
+            Part::Quoted(name) => write!(f, "\"{}\"", name.replace('"', "\"\"")),
+        }
+    }
+}
+
+/// Name of a table, a column or any other sql object
+///
+/// Strings convert to raw identifiers that are rendered verbatim, which keeps
+/// expressions like `count(*)` working. Use `Ident::new` to quote a name, or
+/// `Ident::strict` to reject anything that isn't a plain lowercase name. Builders
+/// created with `IdentPolicy::Strict` check every identifier they are given the
+/// same way, and report the first invalid one from `try_build`.
+///
+/// # Examples
+///
+/// ```
+/// use postgres_querybuilder::SelectBuilder;
+/// use postgres_querybuilder::prelude::{Ident, QueryBuilder, QueryBuilderWithWhere};
+///
+/// let mut builder = SelectBuilder::new(Ident::new("public").dot("User").alias("u"));
+/// builder.select(Ident::new("u").dot("select"));
+/// builder.where_eq(Ident::new("u").dot("weird\"name"), 42);
+///
+/// assert_eq!(builder.get_query(), r#"SELECT "u"."select" FROM "public"."User" AS "u" WHERE "u"."weird""name" = $1"#);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ident {
+    parts: Vec<Part>,
+    alias: Option<Part>,
+}
+
+impl Ident {
+    /// Create a quoted identifier
+    pub fn new(name: &str) -> Self {
+        Ident {
+            parts: vec![Part::Quoted(name.to_string())],
+            alias: None,
+        }
+    }
+
+    /// Create an identifier rendered verbatim, this is what strings convert to
+    pub fn raw(name: &str) -> Self {
+        Ident {
+            parts: vec![Part::Raw(name.to_string())],
+            alias: None,
+        }
+    }
+
+    /// Create a raw identifier, after checking that every dot separated part
+    /// only contains lowercase letters, digits and underscores
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::prelude::Ident;
+    ///
+    /// assert_eq!(Ident::strict("public.users").unwrap().to_string(), "public.users");
+    /// assert!(Ident::strict("users; DROP TABLE users").is_err());
+    /// assert!(Ident::strict("Users").is_err());
+    /// ```
+    pub fn strict(name: &str) -> Result<Self, IdentError> {
+        let valid = name.split('.').all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        });
+        if valid {
+            Ok(Ident::raw(name))
+        } else {
+            Err(IdentError {
+                name: name.to_string(),
+            })
+        }
+    }
+
+    /// Qualify the identifier with a quoted sub name, like `schema.table` or `table.column`
+    pub fn dot(mut self, name: &str) -> Self {
+        self.parts.push(Part::Quoted(name.to_string()));
+        self
+    }
+
+    /// Give the identifier a quoted alias
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.alias = Some(Part::Quoted(alias.to_string()));
+        self
+    }
+}
+
+impl fmt::Display for Ident {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts: Vec<String> = self.parts.iter().map(|part| part.to_string()).collect();
+        write!(f, "{}", parts.join("."))?;
+        if let Some(alias) = self.alias.as_ref() {
+            write!(f, " AS {}", alias)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<&str> for Ident {
+    fn from(name: &str) -> Self {
+        Ident::raw(name)
+    }
+}
+
+impl From<String> for Ident {
+    fn from(name: String) -> Self {
+        Ident::raw(&name)
+    }
+}
+
+impl From<&String> for Ident {
+    fn from(name: &String) -> Self {
+        Ident::raw(name)
+    }
+}
+
+/// Whether a rendered identifier is made of lowercase unquoted parts or quoted parts,
+/// with an optional quoted or lowercase alias, which is what `IdentPolicy::Strict` accepts
+pub(crate) fn is_strict(rendered: &str) -> bool {
+    strict_names(rendered).is_some()
+}
+
+/// Names a column rendered as an identifier accepted by `is_strict` can be referenced by,
+/// its last part then its alias, without their quotes
+pub(crate) fn strict_names(rendered: &str) -> Option<Vec<String>> {
+    let chars: Vec<char> = rendered.chars().collect();
+    let mut start = 0;
+    let mut end = part_end(&chars, start)?;
+    while chars.get(end) == Some(&'.') {
+        start = end + 1;
+        end = part_end(&chars, start)?;
+    }
+    let mut names = vec![unquote(&chars[start..end])];
+    if end < chars.len() {
+        let rest: String = chars[end..].iter().collect();
+        let alias: Vec<char> = rest.strip_prefix(" AS ")?.chars().collect();
+        if part_end(&alias, 0) != Some(alias.len()) {
+            return None;
+        }
+        names.push(unquote(&alias));
+    }
+    Some(names)
+}
+
+fn unquote(part: &[char]) -> String {
+    let part: String = part.iter().collect();
+    match part.strip_prefix('"') {
+        Some(quoted) => quoted[..quoted.len() - 1].replace("\"\"", "\""),
+        None => part,
+    }
+}
+
+/// End of the unquoted or quoted part starting at `start`
+fn part_end(chars: &[char], start: usize) -> Option<usize> {
+    if chars.get(start) == Some(&'"') {
+        let mut index = start + 1;
+        while index < chars.len() {
+            if chars[index] == '"' {
+                if chars.get(index + 1) == Some(&'"') {
+                    index += 2;
+                    continue;
+                }
+                return Some(index + 1);
+            }
+            index += 1;
+        }
+        return None;
+    }
+    let end = chars[start..]
+        .iter()
+        .position(|c| !(c.is_ascii_lowercase() || c.is_ascii_digit() || *c == '_'))
+        .map_or(chars.len(), |position| start + position);
+    if end > start {
+        Some(end)
+    } else {
+        None
+    }
+}
+
+/// Error returned by `Ident::strict` for names that would need quoting
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdentError {
+    pub name: String,
+}
+
+impl fmt::Display for IdentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid unquoted identifier {:?}", self.name)
+    }
+}
+
+impl Error for IdentError {}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[test]
+    fn render() {
+        assert_eq!(Ident::from("users").to_string(), "users");
+        assert_eq!(Ident::new("users").to_string(), "\"users\"");
+        assert_eq!(
+            Ident::raw("public").dot("Users").alias("u").to_string(),
+            "public.\"Users\" AS \"u\""
+        );
+        assert_eq!(Ident::new("a\"b").to_string(), "\"a\"\"b\"");
+    }
+
+    #[test]
+    fn rendered_is_strict() {
+        assert!(is_strict("users"));
+        assert!(is_strict("public.users_2"));
+        assert!(is_strict(
+            &Ident::raw("public").dot("User").alias("u").to_string()
+        ));
+        assert!(is_strict(&Ident::new("a\"b. AS c").to_string()));
+        assert!(is_strict("users AS u"));
+        assert!(!is_strict(""));
+        assert!(!is_strict("public."));
+        assert!(!is_strict("users u"));
+        assert!(!is_strict("count(*)"));
+        assert!(!is_strict("Users"));
+        assert!(!is_strict("\"unterminated"));
+        assert!(!is_strict("users AS "));
+        assert!(!is_strict("users; DROP TABLE users"));
+    }
+
+    #[test]
+    fn rendered_strict_names() {
+        assert_eq!(strict_names("users"), Some(vec!["users".to_string()]));
+        assert_eq!(
+            strict_names("public.\"User\"\"s\" AS u"),
+            Some(vec!["User\"s".to_string(), "u".to_string()])
+        );
+        assert_eq!(strict_names("lower(name)"), None);
+    }
+
+    #[test]
+    fn strict() {
+        assert!(Ident::strict("users").is_ok());
+        assert!(Ident::strict("public.users_2").is_ok());
+        assert!(Ident::strict("").is_err());
+        assert!(Ident::strict("public.").is_err());
+        assert!(Ident::strict("users u").is_err());
+        assert!(Ident::strict("\"users\"").is_err());
+        assert_eq!(
+            Ident::strict("Users").unwrap_err().to_string(),
+            "invalid unquoted identifier \"Users\""
+        );
+    }
+}
@@ -1,16 +1,26 @@
-use crate::bucket::Bucket;
+use crate::bucket::{map_placeholders, Bucket, MAX_PARAMS};
+use crate::clause::{Clause, Sections};
 use crate::prelude::*;
+use crate::SelectBuilder;
 use postgres_types::ToSql;
+use std::collections::{BTreeSet, HashMap};
 
 pub struct InsertBuilder {
-    with_queries: Vec<(String, String)>,
+    with_queries: Vec<String>,
+    recursive: bool,
     table: String,
     fields: Vec<String>,
-    values: Vec<String>,
+    rows: Vec<Vec<String>>,
+    select_query: Option<String>,
+    default_values: bool,
     returning_fields: Vec<String>,
-    upsert_field: Option<String>,
-    upsert_set_fields: Vec<String>,
+    conflict_target: Option<String>,
+    conflict_target_conditions: Vec<String>,
+    conflict_do_nothing: bool,
+    conflict_set_fields: Vec<String>,
+    conflict_conditions: Vec<String>,
     params: Bucket,
+    config: BuilderConfig,
 }
 
 impl InsertBuilder {
@@ -19,7 +29,7 @@ impl InsertBuilder {
     /// # Examples
     ///
     /// ```
-    /// use postgres_querybuilder::InsertBuilder;
+    /// use postgres_querybuilder::{sql, InsertBuilder};
     /// use postgres_querybuilder::prelude::{ QueryBuilder, QueryBuilderWithValues, QueryBuilderWithWhere, QueryWithFields, QueryBuilderWithReturningColumns, QueryBuilderWithOnConflict };
     ///
     /// let mut builder = InsertBuilder::new("users");
@@ -27,9 +37,7 @@ impl InsertBuilder {
     /// builder.field("shape"); // pass in a single field
     /// builder.value(22);
     /// builder.value("rick");
-    /// builder.value_fragment("ST_Transform(ST_GeomFromGeoJSON(?), ?)", vec![
-    ///     "some_geojson", "4263",
-    /// ]);
+    /// builder.value_sql(sql!("ST_Transform(ST_GeomFromGeoJSON(?), ?)", "some_geojson", 4263).unwrap());
     /// // Alternatively you can pass functions and parameters to wrap value
     /// // builder.value_with_fn("some_geojson", vec!["ST_Transform", "ST_GeomFromGeoJSON"], vec![Some("4263"), None]);
     /// builder.on_conflict("id", vec!["username", "alias"]); // upsert clause
@@ -37,44 +45,214 @@ impl InsertBuilder {
     ///
     /// assert_eq!(builder.get_query(), "INSERT INTO users (id, username, shape) VALUES ($1, $2, ST_Transform(ST_GeomFromGeoJSON($3), $4)) ON CONFLICT (id) DO UPDATE SET username = EXCLUDED.username, alias = EXCLUDED.alias RETURNING id");
     /// ```
-    pub fn new(from: &str) -> Self {
+    pub fn new(from: impl Into<Ident>) -> Self {
+        InsertBuilder::with_config(from, BuilderConfig::default())
+    }
+
+    /// Create a new insert builder for a given table with the given settings
+    pub fn with_config(from: impl Into<Ident>, config: BuilderConfig) -> Self {
+        let mut params = Bucket::new();
         InsertBuilder {
             with_queries: vec![],
-            table: from.into(),
+            recursive: false,
+            table: params.ident(from),
             fields: vec![],
-            values: vec![],
+            rows: vec![],
+            select_query: None,
+            default_values: false,
             returning_fields: vec![],
-            upsert_field: None,
-            upsert_set_fields: vec![],
-            params: Bucket::new(),
+            conflict_target: None,
+            conflict_target_conditions: vec![],
+            conflict_do_nothing: false,
+            conflict_set_fields: vec![],
+            conflict_conditions: vec![],
+            params,
+            config,
         }
     }
 
-    pub fn get_values(&mut self) -> &Vec<Box<(dyn ToSql + Sync + 'static)>> {
+    /// Insert the rows returned by a select query, merging its parameters
+    ///
+    /// `try_build` fails when values or default values are also given.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::{InsertBuilder, SelectBuilder};
+    /// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithWhere, QueryWithFields};
+    ///
+    /// let mut select = SelectBuilder::new("users");
+    /// select.select("id");
+    /// select.select("name");
+    /// select.where_lt("last_login", "2020-01-01");
+    /// let mut builder = InsertBuilder::new("archived_users");
+    /// builder.fields(vec!["id", "name"]);
+    /// builder.select(select);
+    ///
+    /// assert_eq!(builder.get_query(), "INSERT INTO archived_users (id, name) SELECT id, name FROM users WHERE last_login < $1");
+    /// ```
+    pub fn select(&mut self, query: SelectBuilder) -> &mut Self {
+        let query = self.add_subquery(query);
+        self.select_query = Some(query);
+        self
+    }
+
+    /// Insert a single row made of the default value of every column
+    ///
+    /// `try_build` fails when fields, values or a select query are also given.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::InsertBuilder;
+    /// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithReturningColumns};
+    ///
+    /// let mut builder = InsertBuilder::new("counters");
+    /// builder.default_values();
+    /// builder.returning(vec!["id"]);
+    ///
+    /// assert_eq!(builder.get_query(), "INSERT INTO counters DEFAULT VALUES RETURNING id");
+    /// ```
+    pub fn default_values(&mut self) -> &mut Self {
+        self.default_values = true;
+        self
+    }
+
+    pub fn get_values(&mut self) -> &Vec<Box<dyn ToSql + Sync + 'static>> {
         &self.params.content
     }
+
+    /// Split the query in several statements so that none of them has more
+    /// parameters than postgres accepts
+    ///
+    /// Rows are spread over the statements, the parameters that don't belong to
+    /// a row, like in `WITH` queries, are bound in every statement.
+    ///
+    /// The query is validated like with `try_build`, a placeholder of the raw sql
+    /// without a bound parameter fails with `QueryBuildError::UnboundPlaceholder`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::InsertBuilder;
+    /// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithValues, QueryWithFields};
+    ///
+    /// let mut builder = InsertBuilder::new("users");
+    /// builder.fields(vec!["id", "name"]);
+    /// for id in 0..40000 {
+    ///     builder.values_row(vec![id.to_string(), format!("user {}", id)]);
+    /// }
+    /// let batches = builder.get_batches().unwrap();
+    ///
+    /// assert_eq!(batches.len(), 2);
+    /// assert_eq!(batches[0].params.len(), 65534);
+    /// assert_eq!(batches[1].params.len(), 14466);
+    /// assert!(batches[1].query.starts_with("INSERT INTO users (id, name) VALUES ($1, $2), ($3, $4)"));
+    /// ```
+    pub fn get_batches(&self) -> Result<Vec<BuiltQuery<'_>>, QueryBuildError> {
+        self.validate()?;
+        let unbound = |index: usize| QueryBuildError::UnboundPlaceholder { index };
+        let mut row_params: Vec<BTreeSet<usize>> = vec![];
+        for row in self.rows.iter() {
+            let mut indexes = BTreeSet::new();
+            for value in row {
+                map_placeholders(value, |index| {
+                    indexes.insert(index);
+                    index
+                });
+            }
+            if let Some(index) = indexes
+                .iter()
+                .find(|index| **index == 0 || **index > self.params.len())
+            {
+                return Err(unbound(*index));
+            }
+            row_params.push(indexes);
+        }
+        let mut shared: BTreeSet<usize> = (1..=self.params.len()).collect();
+        for indexes in row_params.iter() {
+            for index in indexes {
+                shared.remove(index);
+            }
+        }
+        let budget = MAX_PARAMS.saturating_sub(shared.len());
+
+        let mut chunks: Vec<(usize, usize)> = vec![];
+        let mut start = 0;
+        let mut count = 0;
+        for (position, indexes) in row_params.iter().enumerate() {
+            if position > start && count + indexes.len() > budget {
+                chunks.push((start, position));
+                start = position;
+                count = 0;
+            }
+            count += indexes.len();
+        }
+        chunks.push((start, self.rows.len()));
+
+        let refs = self.params.get_refs();
+        chunks
+            .into_iter()
+            .map(|(start, end)| {
+                let mut included = shared.clone();
+                for indexes in row_params[start..end].iter() {
+                    included.extend(indexes);
+                }
+                if included.len() > MAX_PARAMS {
+                    return Err(QueryBuildError::TooManyParams {
+                        found: included.len(),
+                    });
+                }
+                let mapping: HashMap<usize, usize> = included
+                    .iter()
+                    .enumerate()
+                    .map(|(position, index)| (*index, position + 1))
+                    .collect();
+                let mut missing = None;
+                let query =
+                    map_placeholders(&self.render(&self.rows[start..end]), |index| match mapping
+                        .get(&index)
+                    {
+                        Some(position) => *position,
+                        None => *missing.get_or_insert(index),
+                    });
+                if let Some(index) = missing {
+                    return Err(unbound(index));
+                }
+                let params = included.iter().map(|index| refs[index - 1]).collect();
+                Ok(BuiltQuery { query, params })
+            })
+            .collect()
+    }
+
+    fn push_value(&mut self, value: String) {
+        match self.rows.last_mut() {
+            Some(row) => row.push(value),
+            None => self.rows.push(vec![value]),
+        };
+    }
 }
 
 impl InsertBuilder {
     fn with_queries_to_query(&self) -> Option<String> {
-        if self.with_queries.len() > 0 {
-            let result: Vec<String> = self
-                .with_queries
-                .iter()
-                .map(|item| format!("{} AS ({})", item.0, item.1))
-                .collect();
-            Some(format!("WITH {}", result.join(", ")))
+        if !self.with_queries.is_empty() {
+            let keyword = if self.recursive {
+                "WITH RECURSIVE"
+            } else {
+                "WITH"
+            };
+            Some(format!("{} {}", keyword, self.with_queries.join(", ")))
         } else {
             None
         }
     }
 
-    fn from_to_query(&self) -> String {
+    fn table_to_query(&self) -> String {
         format!("INSERT INTO {}", self.table)
     }
 
     fn fields_to_query(&self) -> Option<String> {
-        if self.fields.len() > 0 {
+        if !self.fields.is_empty() {
             let fields_query = self.fields.join(", ");
             Some(format!("({})", fields_query))
         } else {
@@ -82,41 +260,61 @@ impl InsertBuilder {
         }
     }
 
-    fn values_to_query(&self) -> Option<String> {
-        if self.values.len() > 0 {
-            let values_query = self.values.join(", ");
-            Some(format!("VALUES ({})", values_query))
+    fn values_to_query(&self, rows: &[Vec<String>]) -> Option<String> {
+        let rows: Vec<String> = rows
+            .iter()
+            .filter(|row| !row.is_empty())
+            .map(|row| format!("({})", row.join(", ")))
+            .collect();
+        if !rows.is_empty() {
+            Some(format!("VALUES {}", rows.join(", ")))
         } else {
             None
         }
     }
 
+    fn source_to_query(&self, rows: &[Vec<String>]) -> Option<String> {
+        if self.default_values {
+            Some("DEFAULT VALUES".to_string())
+        } else if let Some(query) = self.select_query.as_ref() {
+            Some(query.clone())
+        } else {
+            self.values_to_query(rows)
+        }
+    }
+
     fn on_conflict_query(&self) -> Option<String> {
-        if self.upsert_field.is_some() && self.upsert_set_fields.len() > 0 {
-            let upsert_fields = self
-                .upsert_set_fields
-                .iter()
-                .map(|field| format!("{} = EXCLUDED.{}", field, field))
-                .collect::<Vec<String>>()
-                .join(", ");
-
-            Some(format!(
-                "ON CONFLICT ({}) DO UPDATE SET {}",
-                self.upsert_field.as_ref().unwrap(),
-                upsert_fields
-            ))
-        } else if self.upsert_field.is_some() {
-            Some(format!(
-                "ON CONFLICT ({}) DO NOTHING",
-                self.upsert_field.as_ref().unwrap()
-            ))
+        if self.conflict_target.is_none()
+            && !self.conflict_do_nothing
+            && self.conflict_set_fields.is_empty()
+        {
+            return None;
+        }
+        let mut result = vec!["ON CONFLICT".to_string()];
+        if let Some(target) = self.conflict_target.as_ref() {
+            result.push(target.clone());
+        }
+        if !self.conflict_target_conditions.is_empty() {
+            let conditions = self.conflict_target_conditions.join(" AND ");
+            result.push(format!("WHERE {}", conditions));
+        }
+        if self.conflict_do_nothing || self.conflict_set_fields.is_empty() {
+            result.push("DO NOTHING".to_string());
         } else {
-            None
+            result.push(format!(
+                "DO UPDATE SET {}",
+                self.conflict_set_fields.join(", ")
+            ));
+            if !self.conflict_conditions.is_empty() {
+                let conditions = self.conflict_conditions.join(" AND ");
+                result.push(format!("WHERE {}", conditions));
+            }
         }
+        Some(result.join(" "))
     }
 
     fn returning_fields_to_query(&self) -> Option<String> {
-        if self.returning_fields.len() > 0 {
+        if !self.returning_fields.is_empty() {
             let returning_query = self.returning_fields.join(", ");
             Some(format!("RETURNING {}", returning_query))
         } else {
@@ -130,46 +328,101 @@ impl QueryBuilder for InsertBuilder {
         self.params.push(value)
     }
 
+    fn append_params(&mut self, params: Bucket) -> usize {
+        self.params.append(params)
+    }
+
+    fn params_len(&self) -> usize {
+        self.params.len()
+    }
+
     fn get_query(&self) -> String {
-        let mut result: Vec<String> = vec![];
-        match self.with_queries_to_query() {
-            Some(value) => result.push(value),
-            None => (),
-        };
-        result.push(self.from_to_query());
-        match self.fields_to_query() {
-            Some(value) => result.push(value),
-            None => (),
-        };
-        match self.values_to_query() {
-            Some(value) => result.push(value),
-            None => (),
-        };
-        match self.on_conflict_query() {
-            Some(value) => result.push(value),
-            None => (),
-        };
-        match self.returning_fields_to_query() {
-            Some(value) => result.push(value),
-            None => (),
-        };
-        result.join(" ")
+        self.render(&self.rows)
     }
 
-    fn get_ref_params(self) -> Vec<&'static (dyn ToSql + Sync)> {
+    fn get_ref_params(&self) -> Vec<&(dyn ToSql + Sync)> {
         self.params.get_refs()
     }
+
+    fn into_params(self) -> Bucket {
+        self.params
+    }
+
+    fn validate(&self) -> Result<(), QueryBuildError> {
+        self.config.check_idents(&self.params)?;
+        self.params.check_subqueries()?;
+        let rows: Vec<&Vec<String>> = self.rows.iter().filter(|row| !row.is_empty()).collect();
+        let sources = [
+            !rows.is_empty(),
+            self.select_query.is_some(),
+            self.default_values,
+        ];
+        if sources.iter().filter(|used| **used).count() > 1 {
+            return Err(QueryBuildError::ConflictingValues);
+        }
+        if self.default_values && !self.fields.is_empty() {
+            return Err(QueryBuildError::DefaultValuesWithFields);
+        }
+        if !self.default_values && self.select_query.is_none() {
+            let first = rows.first().ok_or(QueryBuildError::MissingValues)?;
+            let expected = match self.fields.len() {
+                0 => first.len(),
+                count => count,
+            };
+            for (row, values) in rows.iter().enumerate() {
+                if values.len() != expected {
+                    return Err(QueryBuildError::ValuesCountMismatch {
+                        row,
+                        expected,
+                        found: values.len(),
+                    });
+                }
+            }
+        }
+        if !self.conflict_target_conditions.is_empty() {
+            match self.conflict_target.as_ref() {
+                None => return Err(QueryBuildError::MissingConflictTarget),
+                Some(target) if target.starts_with("ON CONSTRAINT ") => {
+                    return Err(QueryBuildError::ConflictWhereOnConstraint)
+                }
+                Some(_) => (),
+            }
+        }
+        if !self.conflict_do_nothing && !self.conflict_set_fields.is_empty() {
+            if self.conflict_target.is_none() {
+                return Err(QueryBuildError::MissingConflictTarget);
+            }
+            if self.fields.is_empty() {
+                return Err(QueryBuildError::ConflictUpdateWithoutColumns);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl InsertBuilder {
+    fn render(&self, rows: &[Vec<String>]) -> String {
+        let mut sections = Sections::new();
+        sections.push(Clause::With, self.with_queries_to_query());
+        sections.push(Clause::Target, self.table_to_query());
+        sections.push(Clause::Columns, self.fields_to_query());
+        sections.push(Clause::Values, self.source_to_query(rows));
+        sections.push(Clause::OnConflict, self.on_conflict_query());
+        sections.push(Clause::Returning, self.returning_fields_to_query());
+        sections.render()
+    }
 }
 
 impl QueryWithFields for InsertBuilder {
-    fn field(&mut self, field: &str) -> &mut Self {
-        self.fields.push(field.to_string());
+    fn field(&mut self, field: impl Into<Ident>) -> &mut Self {
+        let field = self.params.ident(field);
+        self.fields.push(field);
         self
     }
 
-    fn fields(&mut self, fields: Vec<&str>) -> &mut Self {
+    fn fields<I: Into<Ident>>(&mut self, fields: Vec<I>) -> &mut Self {
         for field in fields {
-            self.fields.push(field.to_string());
+            self.field(field);
         }
         self
     }
@@ -178,70 +431,144 @@ impl QueryWithFields for InsertBuilder {
 impl QueryBuilderWithValues for InsertBuilder {
     fn value<T: 'static + ToSql + Sync + Clone>(&mut self, value: T) -> &mut Self {
         let index = self.params.push(value);
-        self.values.push(format!("${}", index));
+        self.push_value(format!("${}", index));
+        self
+    }
+
+    fn value_default(&mut self) -> &mut Self {
+        self.push_value("DEFAULT".to_string());
+        self
+    }
+
+    fn row(&mut self) -> &mut Self {
+        if self.rows.last().map_or(true, |row| !row.is_empty()) {
+            self.rows.push(vec![]);
+        }
+        self
+    }
+
+    fn values_row<T: 'static + ToSql + Sync + Clone>(&mut self, values: Vec<T>) -> &mut Self {
+        self.row();
+        for value in values {
+            self.value(value);
+        }
+        self.row()
+    }
+
+    fn value_sql(&mut self, fragment: Fragment) -> &mut Self {
+        let value = self.add_fragment(fragment);
+        self.push_value(value);
         self
     }
 
-    fn value_fragment<T: 'static + ToSql + Sync + Clone>(&mut self, fragment: &str, values: Vec<T>) -> &mut Self {
-        let mut result: String = "".to_owned();
-        let mut param_count: usize = 0;
+    fn value_fragment<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        fragment: &str,
+        values: Vec<T>,
+    ) -> &mut Self {
+        let mut result = String::new();
+        let mut values = values.into_iter();
         for character in fragment.chars() {
             if character == '?' {
-                let index = if values.get(param_count).is_some() {
-                    self.params.push(values.get(param_count).unwrap().clone())
-                } else {
-                    self.params.push("missing_parameter")
+                let index = match values.next() {
+                    Some(value) => self.params.push(value),
+                    None => self.params.push("missing_parameter"),
                 };
                 result.push_str(&format!("${}", index));
-                param_count = param_count + 1;
             } else {
-                result.push_str(&character.to_string());
+                result.push(character);
             }
         }
-        self.values.push(result);
+        self.push_value(result);
         self
     }
 
-    fn value_with_fn<T: 'static + ToSql + Sync + Clone>(&mut self, value: T, wrapper_fn: Vec<&str>, args: Vec<Option<&str>>) -> &mut Self {
+    fn value_with_fn<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        value: T,
+        wrapper_fn: Vec<&str>,
+        args: Vec<Option<&str>>,
+    ) -> &mut Self {
         let index = self.params.push(value);
         let prefix = wrapper_fn.join("(");
         let mut rev_args = args;
         rev_args.reverse();
-        let suffix = wrapper_fn.iter().enumerate().map(|(idx, _)| {
-            if rev_args.get(idx).is_some() && rev_args.get(idx).unwrap().is_some() {
-                format!(", {})", rev_args.get(idx).unwrap().unwrap())
-            } else {
-                ")".to_string()
-            }
-        }).collect::<String>();
-        self.values.push(format!("{}(${}{}", prefix, index, suffix));
+        let suffix = wrapper_fn
+            .iter()
+            .enumerate()
+            .map(|(idx, _)| {
+                if rev_args.get(idx).is_some() && rev_args.get(idx).unwrap().is_some() {
+                    format!(", {})", rev_args.get(idx).unwrap().unwrap())
+                } else {
+                    ")".to_string()
+                }
+            })
+            .collect::<String>();
+        self.push_value(format!("{}(${}{}", prefix, index, suffix));
         self
     }
 }
 
 impl QueryBuilderWithReturningColumns for InsertBuilder {
-    fn returning(&mut self, fields: Vec<&str>) -> &mut Self {
+    fn returning<I: Into<Ident>>(&mut self, fields: Vec<I>) -> &mut Self {
         for field in fields {
-            self.returning_fields.push(field.to_string());
+            let field = self.params.ident(field);
+            self.returning_fields.push(field);
         }
         self
     }
 }
 
 impl QueryBuilderWithQueries for InsertBuilder {
-    fn with_query(&mut self, name: &str, query: &str) -> &mut Self {
-        self.with_queries.push((name.into(), query.into()));
+    fn with(&mut self, query: WithQuery) -> &mut Self {
+        let item = query.render(&mut self.params);
+        self.with_queries.push(item);
+        self
+    }
+
+    fn with_recursive(&mut self) -> &mut Self {
+        self.recursive = true;
         self
     }
 }
 
 impl QueryBuilderWithOnConflict for InsertBuilder {
-    fn on_conflict(&mut self, conflict_field: &str, update_fields: Vec<&str>) -> &mut Self {
-        self.upsert_field = Some(conflict_field.to_string());
-        self.upsert_set_fields = update_fields
-            .iter()
-            .map(|field| field.to_string())
+    fn on_conflict_columns<I: Into<Ident>>(&mut self, columns: Vec<I>) -> &mut Self {
+        let columns: Vec<String> = columns
+            .into_iter()
+            .map(|column| self.params.ident(column))
             .collect();
+        self.conflict_target = Some(format!("({})", columns.join(", ")));
+        self
+    }
+
+    fn on_conflict_constraint(&mut self, name: impl Into<Ident>) -> &mut Self {
+        let name = self.params.ident(name);
+        self.conflict_target = Some(format!("ON CONSTRAINT {}", name));
+        self
+    }
+
+    fn on_conflict_where(&mut self, condition: &str) -> &mut Self {
+        self.conflict_target_conditions.push(condition.to_string());
+        self
+    }
+
+    fn do_nothing(&mut self) -> &mut Self {
+        self.conflict_do_nothing = true;
+        self.conflict_set_fields.clear();
+        self
+    }
+
+    fn do_update_set_computed(&mut self, field: impl Into<Ident>, value: &str) -> &mut Self {
+        let field = self.params.ident(field);
+        self.conflict_do_nothing = false;
+        self.conflict_set_fields
+            .push(format!("{} = {}", field, value));
+        self
+    }
+
+    fn do_update_where(&mut self, condition: &str) -> &mut Self {
+        self.conflict_conditions.push(condition.to_string());
         self
     }
 }
@@ -256,6 +583,343 @@ pub mod test {
         assert_eq!(builder.get_query(), "INSERT INTO publishers");
     }
 
+    #[test]
+    fn clause_combinations() {
+        for mask in 0..16 {
+            let enabled = |index: usize| mask & (1 << index) != 0;
+            let mut builder = InsertBuilder::new("users");
+            let mut expected = vec![];
+            if enabled(0) {
+                builder.returning(vec!["id"]);
+            }
+            if enabled(1) {
+                builder.do_nothing();
+            }
+            if enabled(2) {
+                builder.field("id");
+                builder.value_default();
+            }
+            if enabled(3) {
+                builder.with_query("tmp", "SELECT 1");
+                expected.push("WITH tmp AS (SELECT 1)");
+            }
+            expected.push("INSERT INTO users");
+            if enabled(2) {
+                expected.push("(id)");
+                expected.push("VALUES (DEFAULT)");
+            }
+            if enabled(1) {
+                expected.push("ON CONFLICT DO NOTHING");
+            }
+            if enabled(0) {
+                expected.push("RETURNING id");
+            }
+            assert_eq!(builder.get_query(), expected.join(" "));
+        }
+    }
+
+    #[test]
+    fn with_rows() {
+        let mut builder = InsertBuilder::new("users");
+        builder.fields(vec!["id", "username"]);
+        builder.value(1).value("rick");
+        builder.row();
+        builder.value(2).value("morty");
+        builder.values_row(vec!["3", "summer"]);
+        builder.row().row();
+        builder.values_row(vec!["4", "beth"]);
+        assert_eq!(
+            builder.get_query(),
+            "INSERT INTO users (id, username) VALUES ($1, $2), ($3, $4), ($5, $6), ($7, $8)"
+        );
+    }
+
+    #[test]
+    fn with_select() {
+        let mut select = SelectBuilder::new("users");
+        select.select("id");
+        select.where_eq("name", "rick");
+        let mut builder = InsertBuilder::new("archives");
+        builder.with_query("ids", "SELECT 1");
+        builder.field("id");
+        builder.select(select);
+        builder.returning(vec!["id"]);
+        assert_eq!(
+            builder.get_query(),
+            "WITH ids AS (SELECT 1) INSERT INTO archives (id) SELECT id FROM users WHERE name = $1 RETURNING id"
+        );
+        assert_eq!(builder.get_ref_params().len(), 1);
+    }
+
+    #[test]
+    fn with_default_values() {
+        let mut builder = InsertBuilder::new("users");
+        builder.default_values();
+        assert_eq!(builder.get_query(), "INSERT INTO users DEFAULT VALUES");
+
+        let mut builder = InsertBuilder::new("users");
+        builder.fields(vec!["id", "username"]);
+        builder.value_default().value("rick");
+        builder.row();
+        builder.value(2).value_default();
+        assert_eq!(
+            builder.get_query(),
+            "INSERT INTO users (id, username) VALUES (DEFAULT, $1), ($2, DEFAULT)"
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn with_value_fragment() {
+        let mut builder = InsertBuilder::new("places");
+        builder.fields(vec!["name", "shape"]);
+        builder.value("home");
+        builder.value_fragment("ST_SetSRID(ST_MakePoint(?, ?), 4326)", vec![2.35, 48.85]);
+        assert_eq!(
+            builder.get_query(),
+            "INSERT INTO places (name, shape) VALUES ($1, ST_SetSRID(ST_MakePoint($2, $3), 4326))"
+        );
+        assert_eq!(builder.get_ref_params().len(), 3);
+    }
+
+    #[test]
+    fn with_conflicting_values() {
+        let mut builder = InsertBuilder::new("users");
+        builder.value(1);
+        builder.default_values();
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::ConflictingValues)
+        );
+
+        let mut builder = InsertBuilder::new("users");
+        builder.field("id");
+        builder.values_row(vec![1]);
+        builder.select(SelectBuilder::new("archived_users"));
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::ConflictingValues)
+        );
+
+        let mut builder = InsertBuilder::new("users");
+        builder.field("id");
+        builder.default_values();
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::DefaultValuesWithFields)
+        );
+    }
+
+    #[test]
+    fn with_row_too_short() {
+        let mut builder = InsertBuilder::new("users");
+        builder.fields(vec!["id", "username"]);
+        builder.values_row(vec![1]);
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::ValuesCountMismatch {
+                row: 0,
+                expected: 2,
+                found: 1
+            })
+        );
+
+        let mut builder = InsertBuilder::new("users");
+        builder.values_row(vec![1, 2]);
+        builder.field("id");
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::ValuesCountMismatch {
+                row: 0,
+                expected: 1,
+                found: 2
+            })
+        );
+        builder.field("username");
+        assert!(builder.try_build().is_ok());
+    }
+
+    #[test]
+    fn with_too_many_params() {
+        let mut builder = InsertBuilder::new("users");
+        builder.field("id");
+        for id in 0..MAX_PARAMS as i32 {
+            builder.values_row(vec![id]);
+        }
+        assert!(builder.try_build().is_ok());
+        builder.values_row(vec![0]);
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::TooManyParams {
+                found: MAX_PARAMS + 1
+            })
+        );
+        assert_eq!(builder.get_batches().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn try_build() {
+        let mut builder = InsertBuilder::new("users");
+        builder.fields(vec!["id", "username"]);
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::MissingValues)
+        );
+        builder.value(1).value("rick").row();
+        builder.value(2);
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::ValuesCountMismatch {
+                row: 1,
+                expected: 2,
+                found: 1
+            })
+        );
+        builder.value("morty");
+        builder.do_update_excluded(vec!["username"]);
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::MissingConflictTarget)
+        );
+        builder.on_conflict_columns(vec!["id"]);
+        let built = builder.try_build().unwrap();
+        assert_eq!(
+            built.query,
+            "INSERT INTO users (id, username) VALUES ($1, $2), ($3, $4) ON CONFLICT (id) DO UPDATE SET username = EXCLUDED.username"
+        );
+        assert_eq!(built.params.len(), 4);
+
+        let mut builder = InsertBuilder::new("users");
+        builder.values_row(vec![1, 2]);
+        builder.values_row(vec![3]);
+        assert_eq!(
+            builder.try_build().err().unwrap().to_string(),
+            "row 1 has 1 values but 2 were expected"
+        );
+
+        let mut builder = InsertBuilder::new("users");
+        builder.value(1);
+        builder.on_conflict("id", vec!["username"]);
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::ConflictUpdateWithoutColumns)
+        );
+
+        let mut builder = InsertBuilder::new("users");
+        builder.default_values();
+        builder.do_nothing();
+        assert!(builder.try_build().is_ok());
+    }
+
+    #[test]
+    fn with_batches() {
+        let mut builder = InsertBuilder::new("users");
+        builder.with_query("ids", "SELECT $1::int AS id");
+        builder.add_param(42);
+        builder.fields(vec!["id", "username", "alias"]);
+        for index in 0..30000 {
+            builder.values_row(vec![index.to_string(), "rick".into(), "morty".into()]);
+        }
+        builder.on_conflict("id", Vec::<&str>::new());
+        let batches = builder.get_batches().unwrap();
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].params.len(), 65533);
+        assert_eq!(batches[1].params.len(), 24469);
+        assert!(batches[1].query.starts_with(
+            "WITH ids AS (SELECT $1::int AS id) INSERT INTO users (id, username, alias) VALUES ($2, $3, $4), ($5, $6, $7),"
+        ));
+        assert!(batches[1]
+            .query
+            .ends_with("($24467, $24468, $24469) ON CONFLICT (id) DO NOTHING"));
+    }
+
+    #[test]
+    fn invalid_batches() {
+        let mut builder = InsertBuilder::new("users");
+        builder.fields(vec!["id", "name"]);
+        builder.values_row(vec![1]);
+        assert_eq!(
+            builder.get_batches().err(),
+            Some(QueryBuildError::ValuesCountMismatch {
+                row: 0,
+                expected: 2,
+                found: 1
+            })
+        );
+
+        let mut builder = InsertBuilder::new("users");
+        builder.field("id");
+        builder.value(1);
+        builder.on_conflict_where("deleted_at IS NULL AND id <> $3");
+        assert_eq!(
+            builder.get_batches().err(),
+            Some(QueryBuildError::MissingConflictTarget)
+        );
+        builder.on_conflict_columns(vec!["id"]).do_nothing();
+        assert_eq!(
+            builder.get_batches().err(),
+            Some(QueryBuildError::UnboundPlaceholder { index: 3 })
+        );
+    }
+
+    #[test]
+    fn with_on_conflict() {
+        let mut builder = InsertBuilder::new("counters");
+        builder.fields(vec!["tenant", "name", "count"]);
+        builder.values_row(vec!["a", "b", "c"]);
+        builder.on_conflict_columns(vec!["tenant", "name"]);
+        builder.on_conflict_where("deleted_at IS NULL");
+        builder.do_update_set_computed("count", "counters.count + EXCLUDED.count");
+        builder.do_update_set("updated_by", "rick");
+        builder.do_update_excluded(vec!["name"]);
+        builder.do_update_where("counters.locked IS FALSE");
+        assert_eq!(
+            builder.get_query(),
+            "INSERT INTO counters (tenant, name, count) VALUES ($1, $2, $3) ON CONFLICT (tenant, name) WHERE deleted_at IS NULL DO UPDATE SET count = counters.count + EXCLUDED.count, updated_by = $4, name = EXCLUDED.name WHERE counters.locked IS FALSE"
+        );
+    }
+
+    #[test]
+    fn with_on_conflict_where_without_columns() {
+        let mut builder = InsertBuilder::new("users");
+        builder.field("email");
+        builder.value("rick@example.com");
+        builder.on_conflict_where("deleted_at IS NULL");
+        builder.do_nothing();
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::MissingConflictTarget)
+        );
+        builder.on_conflict_constraint("users_email_key");
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::ConflictWhereOnConstraint)
+        );
+        builder.on_conflict_columns(vec!["email"]);
+        assert_eq!(
+            builder.try_build().unwrap().query,
+            "INSERT INTO users (email) VALUES ($1) ON CONFLICT (email) WHERE deleted_at IS NULL DO NOTHING"
+        );
+    }
+
+    #[test]
+    fn with_on_conflict_do_nothing() {
+        let mut builder = InsertBuilder::new("users");
+        builder.field("id");
+        builder.value(1);
+        builder.do_nothing();
+        assert_eq!(
+            builder.get_query(),
+            "INSERT INTO users (id) VALUES ($1) ON CONFLICT DO NOTHING"
+        );
+
+        builder.on_conflict_constraint("users_pkey");
+        assert_eq!(
+            builder.get_query(),
+            "INSERT INTO users (id) VALUES ($1) ON CONFLICT ON CONSTRAINT users_pkey DO NOTHING"
+        );
+    }
+
     #[test]
     fn with_fields_and_values() {
         let mut builder = InsertBuilder::new("users");
@@ -263,7 +927,11 @@ pub mod test {
         builder.field("alias");
         builder.value(22);
         builder.value("rick");
-        builder.value_with_fn("some_geojson", vec!["ST_Transform", "ST_GeomFromGeoJSON"], vec![Some("4362"), None]);
+        builder.value_with_fn(
+            "some_geojson",
+            vec!["ST_Transform", "ST_GeomFromGeoJSON"],
+            vec![Some("4362"), None],
+        );
         builder.value("none");
         builder.on_conflict("id", vec!["username", "alias"]);
         builder.returning(vec!["id"]);
@@ -272,4 +940,25 @@ pub mod test {
            "INSERT INTO users (id, username, shape, alias) VALUES ($1, $2, ST_Transform(ST_GeomFromGeoJSON($3), 4362), $4) ON CONFLICT (id) DO UPDATE SET username = EXCLUDED.username, alias = EXCLUDED.alias RETURNING id"
         );
     }
+
+    #[test]
+    fn strict_idents() {
+        let config = BuilderConfig {
+            idents: IdentPolicy::Strict,
+            ..BuilderConfig::default()
+        };
+        let mut builder = InsertBuilder::with_config("users", config);
+        builder.fields(vec!["id", "username"]);
+        builder.values_row(vec!["1", "rick"]);
+        builder.on_conflict("id", vec!["username"]);
+        builder.returning(vec!["id"]);
+        assert!(builder.try_build().is_ok());
+        builder.returning(vec!["id * 2"]);
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::InvalidIdent {
+                name: "id * 2".to_string()
+            })
+        );
+    }
 }
@@ -7,17 +7,40 @@
 #[macro_use]
 extern crate serial_test;
 
+extern crate self as postgres_querybuilder;
+
 pub mod bucket;
+pub mod model;
 pub mod prelude;
+
+#[cfg(feature = "tokio-postgres")]
+mod async_client;
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+mod cache;
+mod clause;
+#[cfg(feature = "postgres")]
+mod client;
+mod condition;
+mod delete_builder;
+mod error;
+mod fragment;
+mod ident;
 mod insert_builder;
+mod policy;
 mod select_builder;
 mod update_builder;
-mod delete_builder;
+mod window;
 
+#[cfg(feature = "tokio-postgres")]
+pub use async_client::AsyncStatementCache;
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+pub use cache::CacheStats;
+#[cfg(feature = "postgres")]
+pub use client::StatementCache;
+pub use delete_builder::DeleteBuilder;
 pub use insert_builder::InsertBuilder;
 pub use select_builder::SelectBuilder;
 pub use update_builder::UpdateBuilder;
-pub use delete_builder::DeleteBuilder;
 
 #[cfg(test)]
 mod test {
@@ -42,6 +65,15 @@ mod test {
                 &[],
             )
             .unwrap();
+        client
+            .execute("DROP TABLE IF EXISTS articles;", &[])
+            .unwrap();
+        client
+            .execute(
+                "CREATE TABLE articles (id SERIAL PRIMARY KEY, user_id INTEGER, title TEXT);",
+                &[],
+            )
+            .unwrap();
     }
 
     fn get_connection() -> Client {
@@ -51,8 +83,12 @@ mod test {
         client
     }
 
-    fn execute<T: QueryBuilder>(builder: T) -> Result<u64, Error> {
+    fn execute<T: QueryBuilder>(builder: &T) -> Result<u64, Error> {
         let mut client = get_connection();
+        execute_on(&mut client, builder)
+    }
+
+    fn execute_on<T: QueryBuilder>(client: &mut Client, builder: &T) -> Result<u64, Error> {
         let stmt = builder.get_query();
         let params = builder.get_ref_params();
         client.execute(stmt.as_str(), &params)
@@ -66,7 +102,7 @@ mod test {
         builder.select("name");
         builder.limit(3);
         builder.offset(1);
-        execute(builder).unwrap();
+        execute(&builder).unwrap();
     }
 
     #[serial]
@@ -76,6 +112,899 @@ mod test {
         builder.select("id");
         builder.select("name");
         builder.where_eq("id", 42);
-        execute(builder).unwrap();
+        execute(&builder).unwrap();
+    }
+
+    #[serial]
+    #[test]
+    fn quoted_identifiers() {
+        let mut client = get_connection();
+        client
+            .execute("DROP TABLE IF EXISTS \"Profiles\";", &[])
+            .unwrap();
+        client
+            .execute(
+                "CREATE TABLE \"Profiles\" (\"userId\" INTEGER, \"order\" TEXT);",
+                &[],
+            )
+            .unwrap();
+        let mut builder = InsertBuilder::new(Ident::new("public").dot("Profiles"));
+        builder.fields(vec![Ident::new("userId"), Ident::new("order")]);
+        builder.value(1);
+        builder.value("first".to_string());
+        assert_eq!(execute_on(&mut client, &builder).unwrap(), 1);
+        let mut builder = SelectBuilder::new(Ident::new("Profiles").alias("p"));
+        builder.select(Ident::new("p").dot("order"));
+        builder.where_eq(Ident::new("p").dot("userId"), 1);
+        let rows = client
+            .query(builder.get_query().as_str(), &builder.get_ref_params())
+            .unwrap();
+        assert_eq!(rows[0].get::<_, String>(0), "first");
+        client.execute("DROP TABLE \"Profiles\";", &[]).unwrap();
+    }
+
+    #[serial]
+    #[test]
+    fn select_distinct_on() {
+        let mut client = get_connection();
+        client
+            .execute(
+                "INSERT INTO articles (user_id, title) VALUES (1, 'a'), (1, 'b'), (2, 'c');",
+                &[],
+            )
+            .unwrap();
+        let mut builder = SelectBuilder::new("articles");
+        builder.distinct_on(vec!["user_id"]);
+        builder.select("title");
+        builder.order_by(Order::Asc("user_id".into()));
+        builder.order_by(Order::Desc("title".into()));
+        let built = builder.try_build().unwrap();
+        let titles: Vec<String> = client
+            .query(built.query.as_str(), &built.params)
+            .unwrap()
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        assert_eq!(titles, vec!["b", "c"]);
+    }
+
+    #[serial]
+    #[test]
+    fn select_having() {
+        let mut client = get_connection();
+        client
+            .execute(
+                "INSERT INTO articles (user_id, title) VALUES (1, 'a'), (1, 'b'), (2, 'c'), (3, 'd');",
+                &[],
+            )
+            .unwrap();
+        let mut builder = SelectBuilder::new("articles");
+        builder.select("user_id");
+        builder.where_ne("title", "d".to_string());
+        builder.group_by("user_id");
+        builder.having_gte("count(*)", 2i64);
+        let ids: Vec<i32> = client
+            .query(builder.get_query().as_str(), &builder.get_ref_params())
+            .unwrap()
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        assert_eq!(ids, vec![1]);
+    }
+
+    #[serial]
+    #[test]
+    fn select_grouping_sets() {
+        let mut client = get_connection();
+        client
+            .execute(
+                "INSERT INTO articles (user_id, title) VALUES (1, 'a'), (1, 'b'), (2, 'a');",
+                &[],
+            )
+            .unwrap();
+        let mut builder = SelectBuilder::new("articles");
+        builder.select("user_id, title, count(*)");
+        builder.group_by_distinct();
+        builder.group_by_element(GroupingElement::rollup(vec!["user_id", "title"]));
+        builder.group_by_element(GroupingElement::grouping_sets(vec![
+            GroupingElement::set(vec!["user_id"]),
+            GroupingElement::set(Vec::<&str>::new()),
+        ]));
+        builder.order_by(Order::Asc("user_id".into()));
+        builder.order_by(Order::Asc("title".into()));
+        let rows: Vec<(Option<i32>, Option<String>, i64)> = client
+            .query(builder.get_query().as_str(), &builder.get_ref_params())
+            .unwrap()
+            .iter()
+            .map(|row| (row.get(0), row.get(1), row.get(2)))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (Some(1), Some("a".into()), 1),
+                (Some(1), Some("b".into()), 1),
+                (Some(1), None, 2),
+                (Some(2), Some("a".into()), 1),
+                (Some(2), None, 1),
+                (None, None, 3),
+            ]
+        );
+    }
+
+    #[serial]
+    #[test]
+    fn select_windows() {
+        let mut client = get_connection();
+        client
+            .execute(
+                "INSERT INTO articles (user_id, title) VALUES (1, 'a'), (1, 'b'), (2, 'c'), (1, 'd');",
+                &[],
+            )
+            .unwrap();
+        let mut builder = SelectBuilder::new("articles");
+        builder.select("title");
+        builder.window(
+            "w",
+            Window::new()
+                .partition_by("user_id")
+                .order_by(Order::Asc("id".into())),
+        );
+        builder.select_over("position", "row_number()", Window::named("w"));
+        builder.select_over(
+            "neighbours",
+            "count(*)",
+            Window::named("w")
+                .frame_between(
+                    FrameUnit::Rows,
+                    FrameBound::Preceding("1".into()),
+                    FrameBound::Following("1".into()),
+                )
+                .exclude(FrameExclusion::CurrentRow),
+        );
+        builder.order_by(Order::Asc("id".into()));
+        let rows: Vec<(String, i64, i64)> = client
+            .query(builder.get_query().as_str(), &builder.get_ref_params())
+            .unwrap()
+            .iter()
+            .map(|row| (row.get(0), row.get(1), row.get(2)))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                ("a".into(), 1, 1),
+                ("b".into(), 2, 2),
+                ("c".into(), 1, 0),
+                ("d".into(), 3, 1),
+            ]
+        );
+    }
+
+    #[serial]
+    #[test]
+    fn select_for_update_skip_locked() {
+        let mut client = get_connection();
+        client
+            .execute(
+                "INSERT INTO users (name) VALUES ('rick'), ('morty'), ('summer');",
+                &[],
+            )
+            .unwrap();
+        let mut builder = SelectBuilder::new("users");
+        builder.select("name");
+        builder.order_by(Order::Asc("id".into()));
+        builder.limit(1);
+        builder.for_update().skip_locked();
+        let built = builder.try_build().unwrap();
+        let mut first = client.transaction().unwrap();
+        let claimed = first
+            .query_one(built.query.as_str(), &built.params)
+            .unwrap();
+        assert_eq!(claimed.get::<_, String>(0), "rick");
+
+        let mut other = Client::connect(get_url().as_str(), NoTls).unwrap();
+        let mut second = other.transaction().unwrap();
+        let claimed = second
+            .query_one(built.query.as_str(), &built.params)
+            .unwrap();
+        assert_eq!(claimed.get::<_, String>(0), "morty");
+
+        let mut builder = SelectBuilder::new("users");
+        builder.where_eq("name", "rick".to_string());
+        builder.for_share().nowait();
+        assert!(second
+            .query(builder.get_query().as_str(), &builder.get_ref_params())
+            .is_err());
+    }
+
+    #[serial]
+    #[test]
+    fn select_where_comparisons() {
+        let mut builder = SelectBuilder::new("users");
+        builder.where_gt("id", 1);
+        builder.where_between("id", 2, 100);
+        builder.where_ilike("name", "ri%".to_string());
+        builder.where_regex("name", "^r".to_string());
+        builder.where_is_distinct_from("name", "morty".to_string());
+        builder.where_is_not_null("name");
+        builder.where_is_false("id IS NULL");
+        execute(&builder).unwrap();
+    }
+
+    #[serial]
+    #[test]
+    fn select_where_in() {
+        let mut client = get_connection();
+        client
+            .execute("INSERT INTO users (name) VALUES ('rick'), ('morty');", &[])
+            .unwrap();
+        let count = |builder: SelectBuilder, client: &mut Client| {
+            client
+                .query(builder.get_query().as_str(), &builder.get_ref_params())
+                .unwrap()
+                .len()
+        };
+
+        let mut builder = SelectBuilder::new("users");
+        builder.where_in("name", vec!["rick".to_string(), "summer".to_string()]);
+        assert_eq!(count(builder, &mut client), 1);
+
+        let mut builder = SelectBuilder::new("users");
+        builder.where_in::<i32>("id", vec![]);
+        assert_eq!(count(builder, &mut client), 0);
+
+        let mut builder = SelectBuilder::new("users");
+        builder.where_not_in::<i32>("id", vec![]);
+        assert_eq!(count(builder, &mut client), 2);
+
+        let mut builder = SelectBuilder::new("users");
+        builder.where_all("id", ">", vec![-1, 0]);
+        builder.where_not_in_list("name", vec!["morty".to_string()]);
+        assert_eq!(count(builder, &mut client), 1);
+
+        let mut builder = SelectBuilder::new("users");
+        builder.where_in_list::<String>("name", vec![]);
+        assert_eq!(count(builder, &mut client), 0);
+    }
+
+    #[serial]
+    #[test]
+    fn select_subqueries() {
+        let mut articles = SelectBuilder::new("articles");
+        articles.select("count(*)");
+        articles.where_condition("articles.user_id = users.id");
+        articles.where_ne("title", "draft".to_string());
+        let mut authors = SelectBuilder::new("articles");
+        authors.select("user_id");
+        authors.where_eq("title", "hello".to_string());
+        let mut exists = SelectBuilder::new("articles");
+        exists.where_condition("articles.user_id = users.id");
+        exists.where_gt("id", 10);
+        let mut builder = SelectBuilder::new("users");
+        builder.select("id");
+        builder.select_subquery("articles_count", articles);
+        builder.where_eq("name", "rick".to_string());
+        builder.where_in_subquery("id", authors);
+        builder.where_exists(exists);
+        execute(&builder).unwrap();
+    }
+
+    #[serial]
+    #[test]
+    fn select_with_queries() {
+        let mut client = get_connection();
+        let mut insert = InsertBuilder::new("users");
+        insert.field("name");
+        insert.value("rick".to_string());
+        insert.returning(vec!["id", "name"]);
+        let mut update = UpdateBuilder::new("articles");
+        update.set("title", "updated".to_string());
+        update.where_eq("user_id", 42);
+        update.returning(vec!["id"]);
+        let mut builder = SelectBuilder::new("inserted");
+        builder.select("inserted.name");
+        builder.with_recursive();
+        builder.with_builder("inserted", insert);
+        builder.with(WithQuery::from_builder("updated", update).materialized());
+        builder.with(
+            WithQuery::new(
+                "numbers",
+                "SELECT 1 UNION ALL SELECT n + 1 FROM numbers WHERE n < 3",
+            )
+            .columns(vec!["n"])
+            .not_materialized(),
+        );
+        builder.where_ne("inserted.name", "morty".to_string());
+        let rows = client
+            .query(builder.get_query().as_str(), &builder.get_ref_params())
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        let name: String = rows[0].get(0);
+        assert_eq!(name, "rick");
+    }
+
+    #[serial]
+    #[test]
+    fn select_where_expr() {
+        let mut builder = SelectBuilder::new("users");
+        builder.where_expr(Condition::eq("id", 42).or(!Condition::eq("name", "rick".to_string())));
+        execute(&builder).unwrap();
+    }
+
+    #[serial]
+    #[test]
+    fn fragments() {
+        let mut client = get_connection();
+        let mut insert = InsertBuilder::new("users");
+        insert.field("name");
+        insert.value_sql(sql!("upper(?) || ?", "rick".to_string(), 42.to_string()).unwrap());
+        execute_on(&mut client, &insert).unwrap();
+        let mut update = UpdateBuilder::new("users");
+        update.set_fragment(
+            "name",
+            sql!("lower(name) || '?' || ?", 1.to_string()).unwrap(),
+        );
+        update.where_fragment(
+            sql!(
+                "id > ? AND NOT to_jsonb(name) ?? ?",
+                0,
+                "missing".to_string()
+            )
+            .unwrap(),
+        );
+        execute_on(&mut client, &update).unwrap();
+        client
+            .execute(
+                "INSERT INTO articles (user_id, title) SELECT id, 'hello' FROM users;",
+                &[],
+            )
+            .unwrap();
+        let mut builder = SelectBuilder::new("users AS u");
+        builder.select_fragment(sql!("u.name || ?::TEXT", "!".to_string()).unwrap());
+        builder.join_fragment(
+            Join::Inner,
+            "articles AS a",
+            sql!("a.user_id = u.id AND a.title = ?", "hello".to_string()).unwrap(),
+        );
+        builder.where_eq("u.id", 1);
+        builder.order_by_fragment(Order::Desc, sql!("length(u.name) + ?", 1).unwrap());
+        let rows = client
+            .query(builder.get_query().as_str(), &builder.get_ref_params())
+            .unwrap();
+        assert_eq!(rows[0].get::<_, String>(0), "rick42?1!");
+    }
+
+    #[serial]
+    #[test]
+    fn select_params_reused() {
+        let mut builder = SelectBuilder::new("users");
+        builder.where_eq("name", "rick".to_string());
+        execute(&builder).unwrap();
+        execute(&builder).unwrap();
+    }
+
+    #[serial]
+    #[test]
+    fn select_inner_join() {
+        let mut builder = SelectBuilder::new("users AS u");
+        builder.inner_join("articles AS a", "a.user_id = u.id");
+        builder.where_eq("u.id", 42);
+        execute(&builder).unwrap();
+    }
+
+    #[serial]
+    #[test]
+    fn select_left_join() {
+        let mut builder = SelectBuilder::new("users");
+        builder.left_join("articles", "articles.user_id = users.id");
+        execute(&builder).unwrap();
+    }
+
+    #[serial]
+    #[test]
+    fn select_left_outer_join() {
+        let mut builder = SelectBuilder::new("users");
+        builder.left_outer_join("articles", "articles.user_id = users.id");
+        execute(&builder).unwrap();
+    }
+
+    #[serial]
+    #[test]
+    fn select_right_join() {
+        let mut builder = SelectBuilder::new("users");
+        builder.right_join("articles", "articles.user_id = users.id");
+        execute(&builder).unwrap();
+    }
+
+    #[serial]
+    #[test]
+    fn select_full_outer_join() {
+        let mut builder = SelectBuilder::new("users");
+        builder.full_outer_join("articles", "articles.user_id = users.id");
+        execute(&builder).unwrap();
+    }
+
+    #[serial]
+    #[test]
+    fn select_cross_join() {
+        let mut builder = SelectBuilder::new("users");
+        builder.cross_join("articles");
+        execute(&builder).unwrap();
+    }
+
+    #[serial]
+    #[test]
+    fn select_natural_join() {
+        let mut builder = SelectBuilder::new("users");
+        builder.natural_join("articles");
+        execute(&builder).unwrap();
+    }
+
+    #[serial]
+    #[test]
+    fn select_join_using() {
+        let mut builder = SelectBuilder::new("users");
+        builder.inner_join_using("articles", vec!["id"]);
+        builder.left_join_using("articles AS other", vec!["id"]);
+        execute(&builder).unwrap();
+    }
+
+    #[serial]
+    #[test]
+    fn insert_batches() {
+        let mut client = get_connection();
+        let mut builder = InsertBuilder::new("users");
+        builder.field("name");
+        for index in 0..70000 {
+            builder.values_row(vec![format!("user {}", index)]);
+        }
+        let batches = builder.get_batches().unwrap();
+        assert_eq!(batches.len(), 2);
+        for batch in batches {
+            client.execute(batch.query.as_str(), &batch.params).unwrap();
+        }
+        let row = client.query_one("SELECT count(*) FROM users", &[]).unwrap();
+        let count: i64 = row.get(0);
+        assert_eq!(count, 70000);
+    }
+
+    #[serial]
+    #[test]
+    fn insert_select_and_defaults() {
+        let mut client = get_connection();
+        let mut builder = InsertBuilder::new("articles");
+        builder.default_values();
+        client
+            .execute(builder.get_query().as_str(), &builder.get_ref_params())
+            .unwrap();
+
+        let mut builder = InsertBuilder::new("users");
+        builder.fields(vec!["id", "name"]);
+        builder.value_default().value("rick".to_string());
+        client
+            .execute(builder.get_query().as_str(), &builder.get_ref_params())
+            .unwrap();
+
+        let mut select = SelectBuilder::new("users");
+        select.select("id");
+        select.select("name");
+        select.where_eq("name", "rick".to_string());
+        let mut builder = InsertBuilder::new("articles");
+        builder.fields(vec!["user_id", "title"]);
+        builder.select(select);
+        builder.returning(vec!["id"]);
+        let rows = client
+            .query(builder.get_query().as_str(), &builder.get_ref_params())
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+    }
+
+    #[serial]
+    #[test]
+    fn insert_on_conflict() {
+        let mut client = get_connection();
+        client
+            .execute(
+                "CREATE UNIQUE INDEX articles_title ON articles (title) WHERE user_id IS NOT NULL;",
+                &[],
+            )
+            .unwrap();
+        for _ in 0..2 {
+            let mut builder = InsertBuilder::new("articles AS a");
+            builder.fields(vec!["user_id", "title"]);
+            builder.value(1).value("hello".to_string());
+            builder.on_conflict_columns(vec!["title"]);
+            builder.on_conflict_where("user_id IS NOT NULL");
+            builder.do_update_set_computed("user_id", "a.user_id + EXCLUDED.user_id");
+            builder.do_update_where("a.user_id < 10");
+            execute_on(&mut client, &builder).unwrap();
+        }
+        let row = client
+            .query_one("SELECT user_id FROM articles", &[])
+            .unwrap();
+        let user_id: i32 = row.get(0);
+        assert_eq!(user_id, 2);
+
+        let mut builder = InsertBuilder::new("users");
+        builder.fields(vec!["id", "name"]);
+        builder.value(1).value("rick".to_string());
+        builder.on_conflict_constraint("users_pkey");
+        builder.do_nothing();
+        execute_on(&mut client, &builder).unwrap();
+        execute_on(&mut client, &builder).unwrap();
+
+        let mut builder = InsertBuilder::new("users");
+        builder.fields(vec!["id", "name"]);
+        builder.value(2).value("rick".to_string());
+        builder.do_nothing();
+        assert_eq!(execute_on(&mut client, &builder).unwrap(), 0);
+    }
+
+    #[serial]
+    #[test]
+    fn update_where_returning() {
+        let mut builder = UpdateBuilder::new("users");
+        builder.set("name", "rick".to_string());
+        builder.from("articles");
+        builder.where_condition("articles.user_id = users.id");
+        builder.where_eq("users.id", 42);
+        builder.returning(vec!["users.id"]);
+        execute(&builder).unwrap();
+    }
+
+    #[serial]
+    #[test]
+    fn delete_and_archive() {
+        let mut client = get_connection();
+        client
+            .execute("INSERT INTO users (name) VALUES ('rick'), ('morty');", &[])
+            .unwrap();
+        client
+            .execute(
+                "INSERT INTO articles (user_id, title) VALUES (1, 'hello'), (2, 'world');",
+                &[],
+            )
+            .unwrap();
+        let mut delete = DeleteBuilder::new("articles");
+        delete.only().alias("a");
+        delete.from("users AS u");
+        delete.where_condition("u.id = a.user_id");
+        delete.where_eq("u.name", "rick".to_string());
+        delete.returning(vec!["a.user_id", "a.title"]);
+        let mut builder = InsertBuilder::new("articles");
+        builder.with_builder("deleted", delete);
+        builder.fields(vec!["user_id", "title"]);
+        let mut select = SelectBuilder::new("deleted");
+        select.select("user_id");
+        select.select("title || $1");
+        select.add_param(" (archived)".to_string());
+        builder.select(select);
+        execute_on(&mut client, &builder).unwrap();
+        let rows = client
+            .query("SELECT title FROM articles ORDER BY title", &[])
+            .unwrap();
+        let titles: Vec<String> = rows.iter().map(|row| row.get(0)).collect();
+        assert_eq!(titles, vec!["hello (archived)", "world"]);
+    }
+
+    #[cfg(feature = "postgres")]
+    #[serial]
+    #[test]
+    fn client_ext() {
+        use postgres::fallible_iterator::FallibleIterator;
+
+        let mut client = get_connection();
+        let mut insert = InsertBuilder::new("users");
+        insert.field("name");
+        insert.values_row(vec!["rick".to_string()]);
+        insert.values_row(vec!["morty".to_string()]);
+        assert_eq!(client.execute_builder(&insert).unwrap(), 2);
+        let mut transaction = client.transaction().unwrap();
+        let mut update = UpdateBuilder::new("users");
+        update.set("name", "summer".to_string());
+        update.where_eq("name", "morty".to_string());
+        update.returning(vec!["id"]);
+        let row = transaction.query_one_builder(&update).unwrap();
+        assert_eq!(row.get::<_, i32>(0), 2);
+        transaction.commit().unwrap();
+        let mut select = SelectBuilder::new("users");
+        select.select("name");
+        select.order_by(Order::Asc("name".into()));
+        let names: Vec<String> = client
+            .query_builder(&select)
+            .unwrap()
+            .iter()
+            .map(|row| row.get(0))
+            .collect();
+        assert_eq!(names, vec!["rick", "summer"]);
+        let names: Vec<String> = client
+            .query_raw_builder(&select)
+            .unwrap()
+            .map(|row| Ok(row.get(0)))
+            .collect()
+            .unwrap();
+        assert_eq!(names, vec!["rick", "summer"]);
+        select.where_eq("name", "morty".to_string());
+        assert!(client.query_opt_builder(&select).unwrap().is_none());
+        let update = UpdateBuilder::new("users");
+        assert!(matches!(
+            client.execute_builder(&update),
+            Err(ExecuteError::Build(QueryBuildError::MissingSet))
+        ));
+    }
+
+    /// Only compiles when the futures of `AsyncClientExt` are `Send`
+    #[cfg(feature = "tokio-postgres")]
+    #[allow(dead_code)]
+    fn spawn_generic_query<C, Q>(client: std::sync::Arc<C>, builder: Q)
+    where
+        C: AsyncClientExt + Send + Sync + 'static,
+        Q: QueryBuilder + Send + Sync + 'static,
+    {
+        tokio::spawn(async move { client.query_builder(&builder).await });
+    }
+
+    #[cfg(all(feature = "derive", feature = "postgres"))]
+    #[serial]
+    #[test]
+    fn derive_from_row() {
+        #[derive(Debug, PartialEq, FromRow)]
+        #[table = "users"]
+        struct User {
+            id: i32,
+            #[column(rename = "name")]
+            nickname: String,
+        }
+
+        #[derive(Debug, PartialEq, FromRow)]
+        #[table = "articles"]
+        struct Article {
+            id: i32,
+            title: Option<String>,
+            #[column(flatten)]
+            author: User,
+        }
+
+        let mut client = get_connection();
+        client
+            .execute("INSERT INTO users (name) VALUES ('rick'), ('morty');", &[])
+            .unwrap();
+        client
+            .execute(
+                "INSERT INTO articles (user_id, title) VALUES (2, 'hello'), (1, NULL);",
+                &[],
+            )
+            .unwrap();
+        let mut builder = SelectBuilder::for_model::<Article>();
+        builder.inner_join("users", "users.id = articles.user_id");
+        builder.order_by(Order::Asc("articles.id".into()));
+        assert_eq!(
+            builder.get_query(),
+            "SELECT articles.id, articles.title, users.id AS author_id, users.name AS author_name FROM articles INNER JOIN users ON users.id = articles.user_id ORDER BY articles.id ASC"
+        );
+        let articles: Vec<Article> = client
+            .query_builder(&builder)
+            .unwrap()
+            .iter()
+            .map(Article::from_row)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            articles,
+            vec![
+                Article {
+                    id: 1,
+                    title: Some("hello".into()),
+                    author: User {
+                        id: 2,
+                        nickname: "morty".into()
+                    },
+                },
+                Article {
+                    id: 2,
+                    title: None,
+                    author: User {
+                        id: 1,
+                        nickname: "rick".into()
+                    },
+                },
+            ]
+        );
+        let mut builder = SelectBuilder::new("articles");
+        builder.select("articles.id");
+        builder.select("users.id AS author_id, users.name AS author_name");
+        builder.inner_join("users", "users.id = articles.user_id");
+        builder.where_eq("articles.id", 1);
+        let row = client.query_one_builder(&builder).unwrap();
+        assert!(Article::from_row(&row).is_err());
+        let mut builder = SelectBuilder::new("users");
+        builder.select("id");
+        builder.where_eq("id", 1);
+        let row = client.query_one_builder(&builder).unwrap();
+        assert!(User::from_row(&row).is_err());
+    }
+
+    #[cfg(all(feature = "derive", feature = "postgres"))]
+    #[serial]
+    #[test]
+    fn derive_insertable_changeset() {
+        #[derive(Insertable)]
+        #[table = "articles"]
+        struct NewArticle {
+            #[column(default)]
+            id: Option<i32>,
+            #[column(rename = "user_id")]
+            author: i32,
+            title: String,
+            #[column(skip)]
+            #[allow(dead_code)]
+            draft: bool,
+        }
+
+        #[derive(Changeset)]
+        #[table = "articles"]
+        struct ArticlePatch {
+            user_id: Option<i32>,
+            title: Option<Option<String>>,
+            #[column(skip)]
+            #[allow(dead_code)]
+            reason: String,
+        }
+
+        let mut client = get_connection();
+        let first = NewArticle {
+            id: None,
+            author: 1,
+            title: "hello".into(),
+            draft: true,
+        };
+        let second = NewArticle {
+            id: Some(42),
+            author: 2,
+            title: "world".into(),
+            draft: false,
+        };
+        let mut builder = first.to_insert();
+        second.insert_values(&mut builder);
+        assert_eq!(
+            builder.get_query(),
+            "INSERT INTO articles (id, user_id, title) VALUES (DEFAULT, $1, $2), ($3, $4, $5)"
+        );
+        assert_eq!(client.execute_builder(&builder).unwrap(), 2);
+
+        let patch = ArticlePatch {
+            user_id: None,
+            title: Some(None),
+            reason: "typo".into(),
+        };
+        let mut builder = patch.to_update();
+        builder.where_eq("id", 42);
+        assert_eq!(
+            builder.get_query(),
+            "UPDATE articles SET title = $1 WHERE id = $2"
+        );
+        assert_eq!(client.execute_builder(&builder).unwrap(), 1);
+        let rows = client
+            .query("SELECT id, user_id, title FROM articles ORDER BY id", &[])
+            .unwrap();
+        let articles: Vec<(i32, i32, Option<String>)> = rows
+            .iter()
+            .map(|row| (row.get(0), row.get(1), row.get(2)))
+            .collect();
+        assert_eq!(articles, vec![(1, 1, Some("hello".into())), (42, 2, None)]);
+    }
+
+    #[cfg(feature = "postgres")]
+    #[serial]
+    #[test]
+    fn statement_cache() {
+        let mut cache = StatementCache::new(get_connection(), 2);
+        for name in ["rick", "morty", "summer"].iter() {
+            let mut insert = InsertBuilder::new("users");
+            insert.field("name");
+            insert.value(name.to_string());
+            cache.execute_builder(&insert).unwrap();
+        }
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 1 });
+        let mut select = SelectBuilder::new("users");
+        select.where_eq("name", "rick".to_string());
+        assert!(cache.query_opt_builder(&select).unwrap().is_some());
+        let mut delete = DeleteBuilder::new("users");
+        delete.where_eq("name", "morty".to_string());
+        assert_eq!(cache.execute_builder(&delete).unwrap(), 1);
+        assert_eq!(cache.len(), 2);
+        let mut insert = InsertBuilder::new("users");
+        insert.field("name");
+        insert.value("morty".to_string());
+        cache.execute_builder(&insert).unwrap();
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 4 });
+        let insert = InsertBuilder::new("users");
+        assert!(matches!(
+            cache.execute_builder(&insert),
+            Err(ExecuteError::Build(QueryBuildError::MissingValues))
+        ));
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 4 });
+    }
+
+    #[cfg(feature = "tokio-postgres")]
+    #[tokio::test]
+    #[serial]
+    async fn async_client_ext() {
+        use futures_util::TryStreamExt;
+
+        let (mut client, connection) =
+            tokio_postgres::connect(get_url().as_str(), tokio_postgres::NoTls)
+                .await
+                .unwrap();
+        tokio::spawn(connection);
+        client
+            .batch_execute("DROP TABLE IF EXISTS users; CREATE TABLE users (id SERIAL PRIMARY KEY, name TEXT UNIQUE NOT NULL);")
+            .await
+            .unwrap();
+        let mut insert = InsertBuilder::new("users");
+        insert.field("name");
+        insert.values_row(vec!["rick".to_string()]);
+        insert.values_row(vec!["morty".to_string()]);
+        assert_eq!(client.execute_builder(&insert).await.unwrap(), 2);
+        let transaction = client.transaction().await.unwrap();
+        let mut update = UpdateBuilder::new("users");
+        update.set("name", "summer".to_string());
+        update.where_eq("name", "morty".to_string());
+        update.returning(vec!["id"]);
+        let row = transaction.query_one_builder(&update).await.unwrap();
+        assert_eq!(row.get::<_, i32>(0), 2);
+        transaction.commit().await.unwrap();
+        let mut select = SelectBuilder::new("users");
+        select.select("name");
+        select.order_by(Order::Asc("name".into()));
+        assert_eq!(client.query_builder(&select).await.unwrap().len(), 2);
+        let names: Vec<String> = client
+            .query_raw_builder(&select)
+            .await
+            .unwrap()
+            .map_ok(|row| row.get(0))
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(names, vec!["rick", "summer"]);
+        select.where_eq("name", "morty".to_string());
+        assert!(client.query_opt_builder(&select).await.unwrap().is_none());
+        let update = UpdateBuilder::new("users");
+        assert!(matches!(
+            client.execute_builder(&update).await,
+            Err(ExecuteError::Build(QueryBuildError::MissingSet))
+        ));
+    }
+
+    #[cfg(feature = "tokio-postgres")]
+    #[tokio::test]
+    #[serial]
+    async fn async_statement_cache() {
+        let (client, connection) =
+            tokio_postgres::connect(get_url().as_str(), tokio_postgres::NoTls)
+                .await
+                .unwrap();
+        tokio::spawn(connection);
+        client
+            .batch_execute("DROP TABLE IF EXISTS users; CREATE TABLE users (id SERIAL PRIMARY KEY, name TEXT UNIQUE NOT NULL);")
+            .await
+            .unwrap();
+        let cache = AsyncStatementCache::new(client, 1);
+        for name in ["rick", "morty"].iter() {
+            let mut insert = InsertBuilder::new("users");
+            insert.field("name");
+            insert.value(name.to_string());
+            cache.execute_builder(&insert).await.unwrap();
+        }
+        let select = SelectBuilder::new("users");
+        assert_eq!(cache.query_builder(&select).await.unwrap().len(), 2);
+        assert_eq!(cache.query_builder(&select).await.unwrap().len(), 2);
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 2 });
+        assert_eq!(cache.len(), 1);
+        let insert = InsertBuilder::new("users");
+        assert!(matches!(
+            cache.execute_builder(&insert).await,
+            Err(ExecuteError::Build(QueryBuildError::MissingValues))
+        ));
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 2 });
     }
 }
@@ -0,0 +1,111 @@
+//! Mapping between structs and rows, usually implemented with `#[derive(FromRow)]`,
+//! `#[derive(Insertable)]` and `#[derive(Changeset)]` from the `derive` feature
+
+use crate::prelude::{QueryBuilderWithSet, QueryBuilderWithValues};
+use crate::{InsertBuilder, UpdateBuilder};
+#[cfg(all(feature = "postgres", not(feature = "tokio-postgres")))]
+pub use postgres::{Error, Row};
+#[cfg(feature = "tokio-postgres")]
+pub use tokio_postgres::{Error, Row};
+
+/// Struct read from the columns of a table
+pub trait Model {
+    /// Table used by `SelectBuilder::for_model`
+    const TABLE: &'static str;
+
+    /// Columns to select, qualified with the table and aliased as `{prefix}{column}`
+    /// when a prefix is given
+    fn columns(prefix: &str) -> Vec<String>;
+}
+
+/// Struct built from a row, available with the `postgres` or `tokio-postgres` feature
+///
+/// # Examples
+///
+/// ```ignore
+/// use postgres_querybuilder::SelectBuilder;
+/// use postgres_querybuilder::prelude::{ClientExt, FromRow};
+///
+/// #[derive(FromRow)]
+/// #[table = "users"]
+/// struct User {
+///     id: i32,
+///     #[column(rename = "full_name")]
+///     name: String,
+///     nickname: Option<String>,
+/// }
+///
+/// let builder = SelectBuilder::for_model::<User>();
+/// let users = client
+///     .query_builder(&builder)?
+///     .iter()
+///     .map(User::from_row)
+///     .collect::<Result<Vec<User>, _>>()?;
+/// ```
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+pub trait FromRow: Model + Sized {
+    fn from_row(row: &Row) -> Result<Self, Error> {
+        Self::from_prefixed_row(row, "")
+    }
+
+    /// Build the struct from the columns named `{prefix}{column}`
+    fn from_prefixed_row(row: &Row, prefix: &str) -> Result<Self, Error>;
+}
+
+/// Struct inserted as a row of a table
+///
+/// # Examples
+///
+/// ```ignore
+/// use postgres_querybuilder::prelude::{Insertable, QueryBuilder};
+///
+/// #[derive(Insertable)]
+/// #[table = "users"]
+/// struct NewUser {
+///     name: String,
+///     #[column(default)]
+///     role: Option<String>,
+/// }
+///
+/// let rick = NewUser { name: "rick".into(), role: Some("admin".into()) };
+/// let morty = NewUser { name: "morty".into(), role: None };
+/// let mut builder = rick.to_insert();
+/// morty.insert_values(&mut builder);
+///
+/// assert_eq!(builder.get_query(), "INSERT INTO users (name, role) VALUES ($1, $2), ($3, DEFAULT)");
+/// ```
+pub trait Insertable {
+    /// Fields written by `insert_values`, in the same order
+    fn insert_fields() -> Vec<String>;
+    /// Add a new row made of the values of the struct
+    fn insert_values<B: QueryBuilderWithValues>(&self, builder: &mut B);
+    /// Create an insert builder with the fields and a single row
+    fn to_insert(&self) -> InsertBuilder;
+}
+
+/// Struct holding changes to apply to the rows of a table
+///
+/// # Examples
+///
+/// ```ignore
+/// use postgres_querybuilder::prelude::{Changeset, QueryBuilder, QueryBuilderWithWhere};
+///
+/// #[derive(Changeset)]
+/// #[table = "users"]
+/// struct UserPatch {
+///     name: Option<String>,
+///     email: Option<String>,
+/// }
+///
+/// let patch = UserPatch { name: None, email: Some("rick@example.com".into()) };
+/// let mut builder = patch.to_update();
+/// builder.where_eq("id", 42);
+///
+/// assert_eq!(builder.get_query(), "UPDATE users SET email = $1 WHERE id = $2");
+/// ```
+pub trait Changeset {
+    /// Set the fields that changed
+    fn apply_changes<B: QueryBuilderWithSet>(&self, builder: &mut B);
+    /// Create an update builder setting the fields that changed
+    fn to_update(&self) -> UpdateBuilder;
+}
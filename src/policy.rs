@@ -0,0 +1,68 @@
+use crate::bucket::Bucket;
+use crate::error::QueryBuildError;
+
+/// Whether an update or delete without conditions can be built
+///
+/// With `Deny`, `try_build` fails with `QueryBuildError::MissingWhere` until
+/// `allow_full_table` is called on the builder, and `get_query` renders a
+/// `WHERE false` condition so that the statement never affects any row.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FullTablePolicy {
+    #[default]
+    Allow,
+    Deny,
+}
+
+/// How the identifiers given to a builder are checked
+///
+/// With `Strict`, `try_build` fails with `QueryBuildError::InvalidIdent` when a raw
+/// identifier isn't a plain lowercase name, which `Ident::strict` describes. Quoted
+/// identifiers built with `Ident::new` are always accepted, expressions go through
+/// fragments or the raw sql methods like `where_condition`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum IdentPolicy {
+    #[default]
+    Raw,
+    Strict,
+}
+
+/// Settings given to a builder when it's created
+///
+/// # Examples
+///
+/// ```
+/// use postgres_querybuilder::DeleteBuilder;
+/// use postgres_querybuilder::prelude::{BuilderConfig, FullTablePolicy, QueryBuildError, QueryBuilder, QueryBuilderWithFullTableGuard};
+///
+/// let config = BuilderConfig {
+///     full_table: FullTablePolicy::Deny,
+///     ..BuilderConfig::default()
+/// };
+/// let mut builder = DeleteBuilder::with_config("sessions", config);
+///
+/// assert_eq!(builder.try_build().err(), Some(QueryBuildError::MissingWhere));
+/// assert_eq!(builder.get_query(), "DELETE FROM sessions WHERE false");
+///
+/// builder.allow_full_table();
+///
+/// assert_eq!(builder.try_build().unwrap().query, "DELETE FROM sessions");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BuilderConfig {
+    /// Policy for updates and deletes without conditions
+    pub full_table: FullTablePolicy,
+    /// Policy for the identifiers given to the builder
+    pub idents: IdentPolicy,
+}
+
+impl BuilderConfig {
+    /// Report the first identifier rejected by the identifier policy
+    pub(crate) fn check_idents(&self, params: &Bucket) -> Result<(), QueryBuildError> {
+        match params.invalid_idents.first() {
+            Some(name) if self.idents == IdentPolicy::Strict => {
+                Err(QueryBuildError::InvalidIdent { name: name.clone() })
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -1,27 +1,183 @@
+#[cfg(feature = "tokio-postgres")]
+pub use crate::async_client::AsyncClientExt;
+use crate::bucket::{shift_placeholders, Bucket, MAX_PARAMS};
+#[cfg(feature = "postgres")]
+pub use crate::client::ClientExt;
+pub use crate::condition::Condition;
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+pub use crate::error::ExecuteError;
+pub use crate::error::QueryBuildError;
+pub use crate::fragment::{Fragment, FragmentError};
+pub use crate::ident::{Ident, IdentError};
+#[cfg(any(feature = "postgres", feature = "tokio-postgres"))]
+pub use crate::model::FromRow;
+pub use crate::model::{Changeset, Insertable, Model};
+pub use crate::policy::{BuilderConfig, FullTablePolicy, IdentPolicy};
+pub use crate::window::{FrameBound, FrameExclusion, FrameUnit, Window};
+#[cfg(feature = "derive")]
+pub use postgres_querybuilder_derive::{Changeset, FromRow, Insertable};
 use postgres_types::ToSql;
+use std::fmt;
 
 pub enum Join {
     Inner(String, String),
     Left(String, String),
     LeftOuter(String, String),
+    Right(String, String),
+    FullOuter(String, String),
+    Cross(String),
+    Natural(String),
+    InnerUsing(String, Vec<String>),
+    LeftUsing(String, Vec<String>),
 }
 
-impl Join {
-    pub fn to_string(&self) -> String {
+impl fmt::Display for Join {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Join::Inner(table, constraint) => format!("INNER JOIN {} ON {}", table, constraint),
-            Join::Left(table, constraint) => format!("LEFT JOIN {} ON {}", table, constraint),
+            Join::Inner(table, constraint) => write!(f, "INNER JOIN {} ON {}", table, constraint),
+            Join::Left(table, constraint) => write!(f, "LEFT JOIN {} ON {}", table, constraint),
             Join::LeftOuter(table, constraint) => {
-                format!("LEFT OUTER JOIN {} ON {}", table, constraint)
+                write!(f, "LEFT OUTER JOIN {} ON {}", table, constraint)
+            }
+            Join::Right(table, constraint) => write!(f, "RIGHT JOIN {} ON {}", table, constraint),
+            Join::FullOuter(table, constraint) => {
+                write!(f, "FULL OUTER JOIN {} ON {}", table, constraint)
+            }
+            Join::Cross(table) => write!(f, "CROSS JOIN {}", table),
+            Join::Natural(table) => write!(f, "NATURAL JOIN {}", table),
+            Join::InnerUsing(table, columns) => {
+                write!(f, "INNER JOIN {} USING ({})", table, columns.join(", "))
+            }
+            Join::LeftUsing(table, columns) => {
+                write!(f, "LEFT JOIN {} USING ({})", table, columns.join(", "))
             }
         }
     }
 }
 
+/// Query rendered by `QueryBuilder::try_build`, with its parameters borrowed from the builder
+pub struct BuiltQuery<'a> {
+    pub query: String,
+    pub params: Vec<&'a (dyn ToSql + Sync)>,
+}
+
 pub trait QueryBuilder {
     fn add_param<T: 'static + ToSql + Sync + Clone>(&mut self, value: T) -> usize;
+    /// Move a bucket of parameters at the end of the builder's parameters
+    ///
+    /// Returns the offset to apply to the placeholders referencing them.
+    fn append_params(&mut self, params: Bucket) -> usize;
+    /// Number of bound parameters, which is also the last placeholder used
+    fn params_len(&self) -> usize;
     fn get_query(&self) -> String;
-    fn get_ref_params(self) -> Vec<&'static (dyn ToSql + Sync)>;
+    /// Borrow the bound parameters, in placeholder order, to pass them to a client
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithWhere};
+    ///
+    /// let mut builder = SelectBuilder::new("users");
+    /// builder.where_eq("id", 42);
+    ///
+    /// assert_eq!(builder.get_ref_params().len(), 1);
+    /// ```
+    fn get_ref_params(&self) -> Vec<&(dyn ToSql + Sync)>;
+    /// Take the bound parameters out of the builder
+    fn into_params(self) -> Bucket;
+
+    /// Check that the query is complete and consistent, nothing is checked by default
+    fn validate(&self) -> Result<(), QueryBuildError> {
+        Ok(())
+    }
+
+    /// Validate the query, then render it along with its parameters
+    ///
+    /// Queries binding more than `MAX_PARAMS` parameters fail with
+    /// `QueryBuildError::TooManyParams`, `InsertBuilder::get_batches` splits large inserts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::UpdateBuilder;
+    /// use postgres_querybuilder::prelude::{QueryBuildError, QueryBuilder, QueryBuilderWithSet, QueryBuilderWithWhere};
+    ///
+    /// let mut builder = UpdateBuilder::new("users");
+    /// builder.where_eq("id", 42);
+    ///
+    /// assert_eq!(builder.try_build().err(), Some(QueryBuildError::MissingSet));
+    ///
+    /// builder.set("name", "rick");
+    /// let built = builder.try_build().unwrap();
+    ///
+    /// assert_eq!(built.query, "UPDATE users SET name = $2 WHERE id = $1");
+    /// assert_eq!(built.params.len(), 2);
+    /// ```
+    fn try_build(&self) -> Result<BuiltQuery<'_>, QueryBuildError> {
+        self.validate()?;
+        let params = self.get_ref_params();
+        if params.len() > MAX_PARAMS {
+            return Err(QueryBuildError::TooManyParams {
+                found: params.len(),
+            });
+        }
+        Ok(BuiltQuery {
+            query: self.get_query(),
+            params,
+        })
+    }
+
+    /// Merge the parameters of another query and return its sql with the placeholders renumbered
+    ///
+    /// An invalid query makes `try_build` fail on this builder with the same error.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithWhere};
+    ///
+    /// let mut builder = SelectBuilder::new("users");
+    /// builder.where_eq("active", true);
+    /// let mut subquery = SelectBuilder::new("orders");
+    /// subquery.select("max(total)");
+    /// subquery.where_eq("status", "paid");
+    /// let subquery = builder.add_subquery(subquery);
+    ///
+    /// assert_eq!(subquery, "SELECT max(total) FROM orders WHERE status = $2");
+    /// assert_eq!(builder.get_ref_params().len(), 2);
+    /// ```
+    fn add_subquery<Q: QueryBuilder>(&mut self, query: Q) -> String {
+        let (sql, params) = take_query(query);
+        let offset = self.append_params(params);
+        shift_placeholders(&sql, offset)
+    }
+
+    /// Render an identifier, remembering it for the identifier policy of the builder
+    fn add_ident(&mut self, ident: impl Into<Ident>) -> String {
+        let mut params = Bucket::new();
+        let ident = params.ident(ident);
+        self.append_params(params);
+        ident
+    }
+
+    /// Merge the parameters of a fragment and return its sql with the placeholders renumbered
+    fn add_fragment(&mut self, fragment: Fragment) -> String {
+        let (sql, params) = fragment.build();
+        let offset = self.append_params(params);
+        shift_placeholders(&sql, offset)
+    }
+}
+
+/// Render a builder and take its parameters, keeping its validation error
+/// so that `try_build` reports it on the builder it is embedded in
+fn take_query<Q: QueryBuilder>(query: Q) -> (String, Bucket) {
+    let error = query.validate().err();
+    let sql = query.get_query();
+    let mut params = query.into_params();
+    params.errors.extend(error);
+    (sql, params)
 }
 
 pub trait QueryBuilderWithWhere: QueryBuilder {
@@ -60,7 +216,12 @@ pub trait QueryBuilderWithWhere: QueryBuilder {
     ///
     /// assert_eq!(builder.get_query(), "SELECT * FROM users WHERE id = $1");
     /// ```
-    fn where_eq<T: 'static + ToSql + Sync + Clone>(&mut self, field: &str, value: T) -> &mut Self {
+    fn where_eq<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        value: T,
+    ) -> &mut Self {
+        let field = self.add_ident(field);
         let index = self.add_param(value);
         let condition = format!("{} = ${}", field, index);
         self.where_condition(condition.as_str());
@@ -82,78 +243,1438 @@ pub trait QueryBuilderWithWhere: QueryBuilder {
     ///
     /// assert_eq!(builder.get_query(), "SELECT * FROM users WHERE id <> $1");
     /// ```
-    fn where_ne<T: 'static + ToSql + Sync + Clone>(&mut self, field: &str, value: T) -> &mut Self {
+    fn where_ne<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        value: T,
+    ) -> &mut Self {
+        let field = self.add_ident(field);
         let index = self.add_param(value);
         let condition = format!("{} <> ${}", field, index);
         self.where_condition(condition.as_str());
         self
     }
-}
 
-pub trait QueryWithFields {
-    fn field(&mut self, field: &str) -> &mut Self;
-    fn fields(&mut self, fields: Vec<&str>) -> &mut Self;
-}
+    /// Add where condition comparing a field to a value with the given operator
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilderWithWhere;
+    ///
+    /// let mut builder = SelectBuilder::new("users");
+    /// builder.where_operator("tags", "@>", vec!["admin".to_string()]);
+    /// builder.where_gte("age", 18);
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM users WHERE tags @> $1 AND age >= $2");
+    /// ```
+    fn where_operator<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        operator: &str,
+        value: T,
+    ) -> &mut Self {
+        let field = self.add_ident(field);
+        let index = self.add_param(value);
+        let condition = format!("{} {} ${}", field, operator, index);
+        self.where_condition(condition.as_str());
+        self
+    }
 
-pub trait QueryBuilderWithGroupBy {
-    fn group_by(&mut self, field: &str) -> &mut Self;
-}
+    /// Add where lower than condition to query
+    fn where_lt<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        value: T,
+    ) -> &mut Self {
+        self.where_operator(field, "<", value)
+    }
 
-pub trait QueryBuilderWithLimit {
-    fn limit(&mut self, limit: i64) -> &mut Self;
-}
+    /// Add where lower than or equal condition to query
+    fn where_lte<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        value: T,
+    ) -> &mut Self {
+        self.where_operator(field, "<=", value)
+    }
 
-pub trait QueryBuilderWithOffset {
-    fn offset(&mut self, offset: i64) -> &mut Self;
-}
+    /// Add where greater than condition to query
+    fn where_gt<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        value: T,
+    ) -> &mut Self {
+        self.where_operator(field, ">", value)
+    }
 
-pub trait QueryBuilderWithJoin {
-    fn inner_join(&mut self, table_name: &str, relation: &str) -> &mut Self;
-    fn left_join(&mut self, table_name: &str, relation: &str) -> &mut Self;
-    fn left_outer_join(&mut self, table_name: &str, relation: &str) -> &mut Self;
-}
+    /// Add where greater than or equal condition to query
+    fn where_gte<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        value: T,
+    ) -> &mut Self {
+        self.where_operator(field, ">=", value)
+    }
 
-pub trait QueryBuilderWithSet {
-    fn set<T: 'static + ToSql + Sync + Clone>(&mut self, field: &str, value: T) -> &mut Self;
-    fn set_computed(&mut self, field: &str, value: &str) -> &mut Self;
-}
+    /// Add where between condition to query, bounds included
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilderWithWhere;
+    ///
+    /// let mut builder = SelectBuilder::new("users");
+    /// builder.where_between("age", 18, 28);
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM users WHERE age BETWEEN $1 AND $2");
+    /// ```
+    fn where_between<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        low: T,
+        high: T,
+    ) -> &mut Self {
+        let field = self.add_ident(field);
+        let low_index = self.add_param(low);
+        let high_index = self.add_param(high);
+        let condition = format!("{} BETWEEN ${} AND ${}", field, low_index, high_index);
+        self.where_condition(condition.as_str());
+        self
+    }
 
-pub trait QueryBuilderWithValues {
-    fn value<T: 'static + ToSql + Sync + Clone>(&mut self, value: T) -> &mut Self;
-    fn value_fragment<T: 'static + ToSql + Sync + Clone>(&mut self, fragment: &str, values: Vec<T>) -> &mut Self;
-    fn value_with_fn<T: 'static + ToSql + Sync + Clone>(&mut self, value: T, wrapper_fn: Vec<&str>, args: Vec<Option<&str>>) -> &mut Self;
-}
+    /// Add where like condition to query
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilderWithWhere;
+    ///
+    /// let mut builder = SelectBuilder::new("users");
+    /// builder.where_like("email", "%@example.com");
+    /// builder.where_not_like("name", "test%");
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM users WHERE email LIKE $1 AND name NOT LIKE $2");
+    /// ```
+    fn where_like<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        pattern: T,
+    ) -> &mut Self {
+        self.where_operator(field, "LIKE", pattern)
+    }
 
-pub trait QueryBuilderWithReturningColumns {
-    fn returning(&mut self, fields: Vec<&str>) -> &mut Self;
-}
+    /// Add where case insensitive like condition to query
+    fn where_ilike<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        pattern: T,
+    ) -> &mut Self {
+        self.where_operator(field, "ILIKE", pattern)
+    }
 
-pub trait QueryBuilderWithOnConflict {
-    fn on_conflict(&mut self, conflict_field: &str, update_fields: Vec<&str>) -> &mut Self;
-}
+    /// Add where not like condition to query
+    fn where_not_like<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        pattern: T,
+    ) -> &mut Self {
+        self.where_operator(field, "NOT LIKE", pattern)
+    }
 
-pub trait QueryBuilderWithFrom {
-    fn from(&mut self, item: &str) -> &mut Self;
-}
+    /// Add where similar to condition to query
+    fn where_similar_to<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        pattern: T,
+    ) -> &mut Self {
+        self.where_operator(field, "SIMILAR TO", pattern)
+    }
 
-pub enum Order {
-    Asc(String),
-    Desc(String),
-}
+    /// Add where posix regular expression match condition to query
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilderWithWhere;
+    ///
+    /// let mut builder = SelectBuilder::new("users");
+    /// builder.where_regex("name", "^ri");
+    /// builder.where_iregex("email", "@EXAMPLE\\.com$");
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM users WHERE name ~ $1 AND email ~* $2");
+    /// ```
+    fn where_regex<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        pattern: T,
+    ) -> &mut Self {
+        self.where_operator(field, "~", pattern)
+    }
 
-impl Order {
-    pub fn to_string(&self) -> String {
-        match self {
-            Order::Asc(column) => format!("{} ASC", column),
-            Order::Desc(column) => format!("{} DESC", column),
+    /// Add where case insensitive posix regular expression match condition to query
+    fn where_iregex<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        pattern: T,
+    ) -> &mut Self {
+        self.where_operator(field, "~*", pattern)
+    }
+
+    /// Add where is distinct from condition to query, treating null as a comparable value
+    fn where_is_distinct_from<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        value: T,
+    ) -> &mut Self {
+        self.where_operator(field, "IS DISTINCT FROM", value)
+    }
+
+    /// Add where is null condition to query
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilderWithWhere;
+    ///
+    /// let mut builder = SelectBuilder::new("users");
+    /// builder.where_is_null("deleted_at");
+    /// builder.where_is_true("active");
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM users WHERE deleted_at IS NULL AND active IS TRUE");
+    /// ```
+    fn where_is_null(&mut self, field: impl Into<Ident>) -> &mut Self {
+        let field = self.add_ident(field);
+        self.where_condition(format!("{} IS NULL", field).as_str())
+    }
+
+    /// Add where is not null condition to query
+    fn where_is_not_null(&mut self, field: impl Into<Ident>) -> &mut Self {
+        let field = self.add_ident(field);
+        self.where_condition(format!("{} IS NOT NULL", field).as_str())
+    }
+
+    /// Add where is true condition to query
+    fn where_is_true(&mut self, field: impl Into<Ident>) -> &mut Self {
+        let field = self.add_ident(field);
+        self.where_condition(format!("{} IS TRUE", field).as_str())
+    }
+
+    /// Add where is false condition to query
+    fn where_is_false(&mut self, field: impl Into<Ident>) -> &mut Self {
+        let field = self.add_ident(field);
+        self.where_condition(format!("{} IS FALSE", field).as_str())
+    }
+
+    /// Add where in condition to query, binding the whole list as a single array parameter
+    ///
+    /// The query text doesn't depend on the number of values, and an empty list never matches.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilderWithWhere;
+    ///
+    /// let mut builder = SelectBuilder::new("users");
+    /// builder.where_in("id", vec![1, 2, 3]);
+    /// builder.where_not_in("name", vec!["rick".to_string()]);
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM users WHERE id = ANY($1) AND name <> ALL($2)");
+    /// assert_eq!(builder.get_ref_params().len(), 2);
+    /// ```
+    fn where_in<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        values: Vec<T>,
+    ) -> &mut Self {
+        self.where_any(field, "=", values)
+    }
+
+    /// Add where not in condition to query, binding the whole list as a single array parameter
+    ///
+    /// An empty list always matches.
+    fn where_not_in<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        values: Vec<T>,
+    ) -> &mut Self {
+        self.where_all(field, "<>", values)
+    }
+
+    /// Add where condition matching when the comparison is true for at least one of the values
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilderWithWhere;
+    ///
+    /// let mut builder = SelectBuilder::new("users");
+    /// builder.where_any("email", "LIKE", vec!["%@example.com", "%@example.org"]);
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM users WHERE email LIKE ANY($1)");
+    /// ```
+    fn where_any<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        operator: &str,
+        values: Vec<T>,
+    ) -> &mut Self {
+        let field = self.add_ident(field);
+        let index = self.add_param(values);
+        let condition = format!("{} {} ANY(${})", field, operator, index);
+        self.where_condition(condition.as_str());
+        self
+    }
+
+    /// Add where condition matching when the comparison is true for all the values
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilderWithWhere;
+    ///
+    /// let mut builder = SelectBuilder::new("users");
+    /// builder.where_all("age", ">", vec![18, 21]);
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM users WHERE age > ALL($1)");
+    /// ```
+    fn where_all<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        operator: &str,
+        values: Vec<T>,
+    ) -> &mut Self {
+        let field = self.add_ident(field);
+        let index = self.add_param(values);
+        let condition = format!("{} {} ALL(${})", field, operator, index);
+        self.where_condition(condition.as_str());
+        self
+    }
+
+    /// Add where in condition to query, with one parameter per value
+    ///
+    /// An empty list renders as `FALSE`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilderWithWhere;
+    ///
+    /// let mut builder = SelectBuilder::new("users");
+    /// builder.where_in_list("id", vec![1, 2]);
+    /// builder.where_in_list::<i32>("role_id", vec![]);
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM users WHERE id IN ($1, $2) AND FALSE");
+    /// ```
+    fn where_in_list<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        values: Vec<T>,
+    ) -> &mut Self {
+        let field = self.add_ident(field);
+        if values.is_empty() {
+            return self.where_condition("FALSE");
         }
+        let placeholders: Vec<String> = values
+            .into_iter()
+            .map(|value| format!("${}", self.add_param(value)))
+            .collect();
+        let condition = format!("{} IN ({})", field, placeholders.join(", "));
+        self.where_condition(condition.as_str())
     }
-}
 
-pub trait QueryBuilderWithOrder {
-    fn order_by(&mut self, field: Order);
-}
+    /// Add where not in condition to query, with one parameter per value
+    ///
+    /// An empty list renders as `TRUE`.
+    fn where_not_in_list<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        values: Vec<T>,
+    ) -> &mut Self {
+        let field = self.add_ident(field);
+        if values.is_empty() {
+            return self.where_condition("TRUE");
+        }
+        let placeholders: Vec<String> = values
+            .into_iter()
+            .map(|value| format!("${}", self.add_param(value)))
+            .collect();
+        let condition = format!("{} NOT IN ({})", field, placeholders.join(", "));
+        self.where_condition(condition.as_str())
+    }
 
-pub trait QueryBuilderWithQueries {
-    fn with_query(&mut self, name: &str, query: &str) -> &mut Self;
+    /// Add where condition checking a field against the rows of a subquery
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilderWithWhere;
+    ///
+    /// let mut subquery = SelectBuilder::new("orders");
+    /// subquery.select("user_id");
+    /// subquery.where_eq("status", "paid");
+    /// let mut builder = SelectBuilder::new("users");
+    /// builder.where_eq("active", true);
+    /// builder.where_in_subquery("id", subquery);
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM users WHERE active = $1 AND id IN (SELECT user_id FROM orders WHERE status = $2)");
+    /// ```
+    fn where_in_subquery<Q: QueryBuilder>(
+        &mut self,
+        field: impl Into<Ident>,
+        query: Q,
+    ) -> &mut Self {
+        let field = self.add_ident(field);
+        let subquery = self.add_subquery(query);
+        self.where_condition(format!("{} IN ({})", field, subquery).as_str())
+    }
+
+    /// Add where condition checking a field isn't in the rows of a subquery
+    fn where_not_in_subquery<Q: QueryBuilder>(
+        &mut self,
+        field: impl Into<Ident>,
+        query: Q,
+    ) -> &mut Self {
+        let field = self.add_ident(field);
+        let subquery = self.add_subquery(query);
+        self.where_condition(format!("{} NOT IN ({})", field, subquery).as_str())
+    }
+
+    /// Add where condition checking a subquery returns at least one row
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilderWithWhere;
+    ///
+    /// let mut subquery = SelectBuilder::new("orders");
+    /// subquery.select("1");
+    /// subquery.where_condition("orders.user_id = users.id");
+    /// subquery.where_eq("status", "paid");
+    /// let mut builder = SelectBuilder::new("users");
+    /// builder.where_exists(subquery);
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM users WHERE EXISTS (SELECT 1 FROM orders WHERE orders.user_id = users.id AND status = $1)");
+    /// ```
+    fn where_exists<Q: QueryBuilder>(&mut self, query: Q) -> &mut Self {
+        let subquery = self.add_subquery(query);
+        self.where_condition(format!("EXISTS ({})", subquery).as_str())
+    }
+
+    /// Add where condition checking a subquery doesn't return any row
+    fn where_not_exists<Q: QueryBuilder>(&mut self, query: Q) -> &mut Self {
+        let subquery = self.add_subquery(query);
+        self.where_condition(format!("NOT EXISTS ({})", subquery).as_str())
+    }
+
+    /// Add a condition tree to query
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::{Condition, QueryBuilder, QueryBuilderWithWhere};
+    ///
+    /// let mut builder = SelectBuilder::new("users");
+    /// builder.where_eq("active", true);
+    /// builder.where_expr(Condition::eq("role", "admin").or(!Condition::eq("name", "rick")));
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM users WHERE active = $1 AND (role = $2 OR NOT (name = $3))");
+    /// ```
+    fn where_expr(&mut self, condition: Condition) -> &mut Self {
+        let (query, params) = condition.build_after(self.params_len());
+        self.append_params(params);
+        self.where_condition(query.as_str())
+    }
+
+    /// Add a sql fragment as a where condition, see the `sql!` macro
+    ///
+    /// Like with `where_condition`, the fragment isn't wrapped in parentheses.
+    fn where_fragment(&mut self, fragment: Fragment) -> &mut Self {
+        let condition = self.add_fragment(fragment);
+        self.where_condition(condition.as_str())
+    }
+}
+
+pub trait QueryWithFields {
+    fn field(&mut self, field: impl Into<Ident>) -> &mut Self;
+    fn fields<I: Into<Ident>>(&mut self, fields: Vec<I>) -> &mut Self;
+}
+
+/// Item of a `GROUP BY` clause
+///
+/// # Examples
+///
+/// ```
+/// use postgres_querybuilder::prelude::GroupingElement;
+///
+/// let element = GroupingElement::grouping_sets(vec![
+///     GroupingElement::set(vec!["brand"]),
+///     GroupingElement::set(vec!["brand", "size"]),
+///     GroupingElement::set(Vec::<&str>::new()),
+/// ]);
+///
+/// assert_eq!(element.to_string(), "GROUPING SETS ((brand), (brand, size), ())");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GroupingElement {
+    /// Plain expression
+    Expr(String),
+    /// Parenthesized list of expressions, `()` when empty
+    Set(Vec<String>),
+    /// `ROLLUP (...)` of expressions or sets
+    Rollup(Vec<GroupingElement>),
+    /// `CUBE (...)` of expressions or sets
+    Cube(Vec<GroupingElement>),
+    /// `GROUPING SETS (...)` of any other elements
+    GroupingSets(Vec<GroupingElement>),
+}
+
+impl GroupingElement {
+    /// Create a list of expressions grouped together
+    pub fn set<I: Into<Ident>>(expressions: Vec<I>) -> Self {
+        GroupingElement::Set(Self::expressions(expressions))
+    }
+
+    /// Create a `ROLLUP`, grouping by every prefix of the expressions
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::prelude::GroupingElement;
+    ///
+    /// assert_eq!(GroupingElement::rollup(vec!["year", "month"]).to_string(), "ROLLUP (year, month)");
+    /// ```
+    pub fn rollup<I: Into<Ident>>(expressions: Vec<I>) -> Self {
+        GroupingElement::Rollup(Self::exprs(expressions))
+    }
+
+    /// Create a `CUBE`, grouping by every subset of the expressions
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::prelude::GroupingElement;
+    ///
+    /// assert_eq!(GroupingElement::cube(vec!["brand", "size"]).to_string(), "CUBE (brand, size)");
+    /// ```
+    pub fn cube<I: Into<Ident>>(expressions: Vec<I>) -> Self {
+        GroupingElement::Cube(Self::exprs(expressions))
+    }
+
+    /// Create `GROUPING SETS` out of other elements
+    pub fn grouping_sets(elements: Vec<GroupingElement>) -> Self {
+        GroupingElement::GroupingSets(elements)
+    }
+
+    fn expressions<I: Into<Ident>>(expressions: Vec<I>) -> Vec<String> {
+        expressions
+            .into_iter()
+            .map(|item| item.into().to_string())
+            .collect()
+    }
+
+    fn exprs<I: Into<Ident>>(expressions: Vec<I>) -> Vec<GroupingElement> {
+        Self::expressions(expressions)
+            .into_iter()
+            .map(GroupingElement::Expr)
+            .collect()
+    }
+
+    /// Expressions of the element and of the elements it contains
+    pub(crate) fn items(&self) -> Vec<&String> {
+        match self {
+            GroupingElement::Expr(expression) => vec![expression],
+            GroupingElement::Set(expressions) => expressions.iter().collect(),
+            GroupingElement::Rollup(elements)
+            | GroupingElement::Cube(elements)
+            | GroupingElement::GroupingSets(elements) => elements
+                .iter()
+                .flat_map(|element| element.items())
+                .collect(),
+        }
+    }
+
+    fn join(elements: &[GroupingElement]) -> String {
+        let result: Vec<String> = elements.iter().map(|element| element.to_string()).collect();
+        result.join(", ")
+    }
+}
+
+impl<I: Into<Ident>> From<I> for GroupingElement {
+    fn from(expression: I) -> Self {
+        GroupingElement::Expr(expression.into().to_string())
+    }
+}
+
+impl fmt::Display for GroupingElement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GroupingElement::Expr(expression) => write!(f, "{}", expression),
+            GroupingElement::Set(expressions) => write!(f, "({})", expressions.join(", ")),
+            GroupingElement::Rollup(elements) => write!(f, "ROLLUP ({})", Self::join(elements)),
+            GroupingElement::Cube(elements) => write!(f, "CUBE ({})", Self::join(elements)),
+            GroupingElement::GroupingSets(elements) => {
+                write!(f, "GROUPING SETS ({})", Self::join(elements))
+            }
+        }
+    }
+}
+
+pub trait QueryBuilderWithGroupBy {
+    /// Add a grouping element to the `GROUP BY` clause
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::{GroupingElement, QueryBuilder, QueryBuilderWithGroupBy};
+    ///
+    /// let mut builder = SelectBuilder::new("sales");
+    /// builder.select("country, year, month, sum(total)");
+    /// builder.group_by_element("country");
+    /// builder.group_by_element(GroupingElement::rollup(vec!["year", "month"]));
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT country, year, month, sum(total) FROM sales GROUP BY country, ROLLUP (year, month)");
+    /// ```
+    fn group_by_element(&mut self, element: impl Into<GroupingElement>) -> &mut Self;
+
+    /// Remove the duplicate grouping sets generated by the grouping elements,
+    /// requires postgres 14 or later
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::{GroupingElement, QueryBuilder, QueryBuilderWithGroupBy};
+    ///
+    /// let mut builder = SelectBuilder::new("sales");
+    /// builder.group_by_distinct();
+    /// builder.group_by_element(GroupingElement::rollup(vec!["a", "b"]));
+    /// builder.group_by_element(GroupingElement::rollup(vec!["a", "c"]));
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM sales GROUP BY DISTINCT ROLLUP (a, b), ROLLUP (a, c)");
+    /// ```
+    fn group_by_distinct(&mut self) -> &mut Self;
+
+    fn group_by(&mut self, field: impl Into<Ident>) -> &mut Self {
+        self.group_by_element(GroupingElement::from(field))
+    }
+}
+
+pub trait QueryBuilderWithHaving: QueryBuilder {
+    /// Add a raw having condition, filtering the groups after `GROUP BY`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithGroupBy, QueryBuilderWithHaving};
+    ///
+    /// let mut builder = SelectBuilder::new("articles");
+    /// builder.select("user_id");
+    /// builder.group_by("user_id");
+    /// builder.having_condition("bool_or(published)");
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT user_id FROM articles GROUP BY user_id HAVING bool_or(published)");
+    /// ```
+    fn having_condition(&mut self, raw: &str) -> &mut Self;
+
+    /// Add having equal condition to query
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithGroupBy, QueryBuilderWithHaving, QueryBuilderWithWhere};
+    ///
+    /// let mut builder = SelectBuilder::new("articles");
+    /// builder.select("user_id");
+    /// builder.where_eq("published", true);
+    /// builder.group_by("user_id");
+    /// builder.having_eq("count(*)", 3i64);
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT user_id FROM articles WHERE published = $1 GROUP BY user_id HAVING count(*) = $2");
+    /// ```
+    fn having_eq<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        expression: &str,
+        value: T,
+    ) -> &mut Self {
+        self.having_operator(expression, "=", value)
+    }
+
+    /// Add having not equal condition to query
+    fn having_ne<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        expression: &str,
+        value: T,
+    ) -> &mut Self {
+        self.having_operator(expression, "<>", value)
+    }
+
+    /// Add having condition comparing an aggregate with any binary operator
+    ///
+    /// The expression is raw sql, like with `having_condition`, so aggregates are
+    /// accepted by `IdentPolicy::Strict`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithGroupBy, QueryBuilderWithHaving};
+    ///
+    /// let mut builder = SelectBuilder::new("orders");
+    /// builder.select("user_id");
+    /// builder.group_by("user_id");
+    /// builder.having_operator("array_agg(status)", "@>", vec!["paid".to_string()]);
+    /// builder.having_gte("sum(total)", 100);
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT user_id FROM orders GROUP BY user_id HAVING array_agg(status) @> $1 AND sum(total) >= $2");
+    /// ```
+    fn having_operator<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        expression: &str,
+        operator: &str,
+        value: T,
+    ) -> &mut Self {
+        let index = self.add_param(value);
+        let condition = format!("{} {} ${}", expression, operator, index);
+        self.having_condition(condition.as_str());
+        self
+    }
+
+    /// Add having lower than condition to query
+    fn having_lt<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        expression: &str,
+        value: T,
+    ) -> &mut Self {
+        self.having_operator(expression, "<", value)
+    }
+
+    /// Add having lower than or equal condition to query
+    fn having_lte<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        expression: &str,
+        value: T,
+    ) -> &mut Self {
+        self.having_operator(expression, "<=", value)
+    }
+
+    /// Add having greater than condition to query
+    fn having_gt<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        expression: &str,
+        value: T,
+    ) -> &mut Self {
+        self.having_operator(expression, ">", value)
+    }
+
+    /// Add having greater than or equal condition to query
+    fn having_gte<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        expression: &str,
+        value: T,
+    ) -> &mut Self {
+        self.having_operator(expression, ">=", value)
+    }
+
+    /// Add a condition tree to the having clause
+    ///
+    /// The fields of the conditions are identifiers, use `Condition::raw` to compare
+    /// aggregates with `IdentPolicy::Strict`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::{Condition, QueryBuilder, QueryBuilderWithGroupBy, QueryBuilderWithHaving};
+    ///
+    /// let mut builder = SelectBuilder::new("orders");
+    /// builder.select("user_id");
+    /// builder.group_by("user_id");
+    /// builder.having_expr(Condition::eq("count(*)", 1i64).or(Condition::ne("max(status)", "paid")));
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT user_id FROM orders GROUP BY user_id HAVING (count(*) = $1 OR max(status) <> $2)");
+    /// ```
+    fn having_expr(&mut self, condition: Condition) -> &mut Self {
+        let (query, params) = condition.build_after(self.params_len());
+        self.append_params(params);
+        self.having_condition(query.as_str())
+    }
+
+    /// Add a sql fragment as a having condition, see the `sql!` macro
+    ///
+    /// Like with `having_condition`, the fragment isn't wrapped in parentheses.
+    fn having_fragment(&mut self, fragment: Fragment) -> &mut Self {
+        let condition = self.add_fragment(fragment);
+        self.having_condition(condition.as_str())
+    }
+}
+
+pub trait QueryBuilderWithLimit {
+    fn limit(&mut self, limit: i64) -> &mut Self;
+}
+
+pub trait QueryBuilderWithOffset {
+    fn offset(&mut self, offset: i64) -> &mut Self;
+}
+
+pub trait QueryBuilderWithJoin {
+    /// Add a join to the query, joins are rendered in the order they are added
+    ///
+    /// The table name can contain an alias, like `users AS u`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::{Join, QueryBuilder, QueryBuilderWithJoin};
+    ///
+    /// let mut builder = SelectBuilder::new("articles AS a");
+    /// builder.join(Join::Inner("users AS u".into(), "u.id = a.user_id".into()));
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM articles AS a INNER JOIN users AS u ON u.id = a.user_id");
+    /// ```
+    fn join(&mut self, join: Join) -> &mut Self;
+
+    fn inner_join(&mut self, table_name: impl Into<Ident>, relation: &str) -> &mut Self
+    where
+        Self: QueryBuilder,
+    {
+        let table_name = self.add_ident(table_name);
+        self.join(Join::Inner(table_name.to_string(), relation.to_string()))
+    }
+
+    fn left_join(&mut self, table_name: impl Into<Ident>, relation: &str) -> &mut Self
+    where
+        Self: QueryBuilder,
+    {
+        let table_name = self.add_ident(table_name);
+        self.join(Join::Left(table_name.to_string(), relation.to_string()))
+    }
+
+    fn left_outer_join(&mut self, table_name: impl Into<Ident>, relation: &str) -> &mut Self
+    where
+        Self: QueryBuilder,
+    {
+        let table_name = self.add_ident(table_name);
+        self.join(Join::LeftOuter(
+            table_name.to_string(),
+            relation.to_string(),
+        ))
+    }
+
+    fn right_join(&mut self, table_name: impl Into<Ident>, relation: &str) -> &mut Self
+    where
+        Self: QueryBuilder,
+    {
+        let table_name = self.add_ident(table_name);
+        self.join(Join::Right(table_name.to_string(), relation.to_string()))
+    }
+
+    fn full_outer_join(&mut self, table_name: impl Into<Ident>, relation: &str) -> &mut Self
+    where
+        Self: QueryBuilder,
+    {
+        let table_name = self.add_ident(table_name);
+        self.join(Join::FullOuter(
+            table_name.to_string(),
+            relation.to_string(),
+        ))
+    }
+
+    fn cross_join(&mut self, table_name: impl Into<Ident>) -> &mut Self
+    where
+        Self: QueryBuilder,
+    {
+        let table_name = self.add_ident(table_name);
+        self.join(Join::Cross(table_name.to_string()))
+    }
+
+    fn natural_join(&mut self, table_name: impl Into<Ident>) -> &mut Self
+    where
+        Self: QueryBuilder,
+    {
+        let table_name = self.add_ident(table_name);
+        self.join(Join::Natural(table_name.to_string()))
+    }
+
+    /// Add an inner join on columns sharing the same name in both tables
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithJoin};
+    ///
+    /// let mut builder = SelectBuilder::new("articles");
+    /// builder.inner_join_using("comments", vec!["article_id", "author_id"]);
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM articles INNER JOIN comments USING (article_id, author_id)");
+    /// ```
+    fn inner_join_using<I: Into<Ident>>(
+        &mut self,
+        table_name: impl Into<Ident>,
+        columns: Vec<I>,
+    ) -> &mut Self
+    where
+        Self: QueryBuilder,
+    {
+        let table_name = self.add_ident(table_name);
+        let columns = columns
+            .into_iter()
+            .map(|column| self.add_ident(column))
+            .collect();
+        self.join(Join::InnerUsing(table_name.to_string(), columns))
+    }
+
+    fn left_join_using<I: Into<Ident>>(
+        &mut self,
+        table_name: impl Into<Ident>,
+        columns: Vec<I>,
+    ) -> &mut Self
+    where
+        Self: QueryBuilder,
+    {
+        let table_name = self.add_ident(table_name);
+        let columns = columns
+            .into_iter()
+            .map(|column| self.add_ident(column))
+            .collect();
+        self.join(Join::LeftUsing(table_name.to_string(), columns))
+    }
+
+    /// Add a join whose constraint is a sql fragment, `join` is the variant to use
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::{sql, SelectBuilder};
+    /// use postgres_querybuilder::prelude::{Join, QueryBuilder, QueryBuilderWithJoin, QueryBuilderWithWhere};
+    ///
+    /// let mut builder = SelectBuilder::new("articles AS a");
+    /// builder.where_eq("a.published", true);
+    /// builder.join_fragment(Join::Left, "users AS u", sql!("u.id = a.user_id AND u.role = ?", "admin").unwrap());
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM articles AS a LEFT JOIN users AS u ON u.id = a.user_id AND u.role = $2 WHERE a.published = $1");
+    /// ```
+    fn join_fragment(
+        &mut self,
+        join: impl FnOnce(String, String) -> Join,
+        table_name: impl Into<Ident>,
+        fragment: Fragment,
+    ) -> &mut Self
+    where
+        Self: QueryBuilder,
+    {
+        let table_name = self.add_ident(table_name);
+        let constraint = self.add_fragment(fragment);
+        self.join(join(table_name.to_string(), constraint))
+    }
+}
+
+pub trait QueryBuilderWithFullTableGuard {
+    /// Choose whether the statement can be built without conditions
+    ///
+    /// New builders get the policy of their `BuilderConfig`, `Allow` by default.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::UpdateBuilder;
+    /// use postgres_querybuilder::prelude::*;
+    ///
+    /// let mut builder = UpdateBuilder::new("users");
+    /// builder.full_table_policy(FullTablePolicy::Deny);
+    /// builder.set("active", false);
+    ///
+    /// assert_eq!(builder.try_build().err(), Some(QueryBuildError::MissingWhere));
+    ///
+    /// builder.where_eq("id", 42);
+    ///
+    /// assert!(builder.try_build().is_ok());
+    /// ```
+    fn full_table_policy(&mut self, policy: FullTablePolicy) -> &mut Self;
+
+    /// Explicitly allow the statement to affect every row of the table
+    fn allow_full_table(&mut self) -> &mut Self {
+        self.full_table_policy(FullTablePolicy::Allow)
+    }
+}
+
+pub trait QueryBuilderWithSet {
+    fn set<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        value: T,
+    ) -> &mut Self;
+    fn set_computed(&mut self, field: impl Into<Ident>, value: &str) -> &mut Self;
+
+    /// Set a field to the result of a sql fragment
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::{sql, UpdateBuilder};
+    /// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithSet, QueryBuilderWithWhere};
+    ///
+    /// let mut builder = UpdateBuilder::new("users");
+    /// builder.set("name", "rick");
+    /// builder.set_fragment("score", sql!("score * ? + ?", 2, 10).unwrap());
+    /// builder.where_eq("id", 42);
+    ///
+    /// assert_eq!(builder.get_query(), "UPDATE users SET name = $1, score = score * $2 + $3 WHERE id = $4");
+    /// ```
+    fn set_fragment(&mut self, field: impl Into<Ident>, fragment: Fragment) -> &mut Self
+    where
+        Self: QueryBuilder,
+    {
+        let value = self.add_fragment(fragment);
+        self.set_computed(field, value.as_str())
+    }
+}
+
+pub trait QueryBuilderWithValues {
+    fn value<T: 'static + ToSql + Sync + Clone>(&mut self, value: T) -> &mut Self;
+    /// Add the `DEFAULT` keyword, inserting the default value of the column
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::InsertBuilder;
+    /// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithValues, QueryWithFields};
+    ///
+    /// let mut builder = InsertBuilder::new("users");
+    /// builder.fields(vec!["id", "name"]);
+    /// builder.value_default();
+    /// builder.value("rick");
+    ///
+    /// assert_eq!(builder.get_query(), "INSERT INTO users (id, name) VALUES (DEFAULT, $1)");
+    /// ```
+    fn value_default(&mut self) -> &mut Self;
+    /// Start a new row, the following values are added to it
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::InsertBuilder;
+    /// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithValues, QueryWithFields};
+    ///
+    /// let mut builder = InsertBuilder::new("users");
+    /// builder.fields(vec!["id", "name"]);
+    /// builder.value(1).value("rick");
+    /// builder.row();
+    /// builder.value(2).value("morty");
+    ///
+    /// assert_eq!(builder.get_query(), "INSERT INTO users (id, name) VALUES ($1, $2), ($3, $4)");
+    /// ```
+    fn row(&mut self) -> &mut Self;
+    /// Add a complete row of values
+    ///
+    /// `try_build` fails with `QueryBuildError::ValuesCountMismatch` if the row doesn't
+    /// have one value per field.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::InsertBuilder;
+    /// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithValues, QueryWithFields};
+    ///
+    /// let mut builder = InsertBuilder::new("users");
+    /// builder.fields(vec!["first_name", "last_name"]);
+    /// builder.values_row(vec!["rick", "sanchez"]);
+    /// builder.values_row(vec!["morty", "smith"]);
+    ///
+    /// assert_eq!(builder.get_query(), "INSERT INTO users (first_name, last_name) VALUES ($1, $2), ($3, $4)");
+    /// ```
+    fn values_row<T: 'static + ToSql + Sync + Clone>(&mut self, values: Vec<T>) -> &mut Self;
+    /// Add a value computed by a sql fragment
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::{sql, InsertBuilder};
+    /// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithValues, QueryWithFields};
+    ///
+    /// let mut builder = InsertBuilder::new("places");
+    /// builder.fields(vec!["name", "shape"]);
+    /// builder.value("home");
+    /// builder.value_sql(sql!("ST_SetSRID(ST_MakePoint(?, ?), ?)", 2.35, 48.85, 4326).unwrap());
+    ///
+    /// assert_eq!(builder.get_query(), "INSERT INTO places (name, shape) VALUES ($1, ST_SetSRID(ST_MakePoint($2, $3), $4))");
+    /// ```
+    fn value_sql(&mut self, fragment: Fragment) -> &mut Self;
+    /// Add a value computed by sql where every `?` is replaced by the next value,
+    /// missing values are bound as `"missing_parameter"`
+    #[deprecated(note = "use `value_sql` with the `sql!` macro")]
+    fn value_fragment<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        fragment: &str,
+        values: Vec<T>,
+    ) -> &mut Self;
+    fn value_with_fn<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        value: T,
+        wrapper_fn: Vec<&str>,
+        args: Vec<Option<&str>>,
+    ) -> &mut Self;
+}
+
+pub trait QueryBuilderWithReturningColumns {
+    fn returning<I: Into<Ident>>(&mut self, fields: Vec<I>) -> &mut Self;
+}
+
+pub trait QueryBuilderWithOnConflict {
+    /// Set the conflict target to the given columns
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::InsertBuilder;
+    /// use postgres_querybuilder::prelude::*;
+    ///
+    /// let mut builder = InsertBuilder::new("visits");
+    /// builder.fields(vec!["page", "day", "count"]);
+    /// builder.values_row(vec!["/", "2020-01-01", "1"]);
+    /// builder.on_conflict_columns(vec!["page", "day"]);
+    /// builder.do_update_set_computed("count", "visits.count + EXCLUDED.count");
+    ///
+    /// assert_eq!(builder.get_query(), "INSERT INTO visits (page, day, count) VALUES ($1, $2, $3) ON CONFLICT (page, day) DO UPDATE SET count = visits.count + EXCLUDED.count");
+    /// ```
+    fn on_conflict_columns<I: Into<Ident>>(&mut self, columns: Vec<I>) -> &mut Self;
+
+    /// Set the conflict target to a named constraint
+    fn on_conflict_constraint(&mut self, name: impl Into<Ident>) -> &mut Self;
+
+    /// Add a predicate to the conflict target, to infer a partial unique index
+    ///
+    /// `try_build` fails unless the conflict target is a list of columns.
+    fn on_conflict_where(&mut self, condition: &str) -> &mut Self;
+
+    /// Skip the rows that conflict, a conflict target isn't required
+    fn do_nothing(&mut self) -> &mut Self;
+
+    /// Add an expression to the `DO UPDATE SET` list
+    fn do_update_set_computed(&mut self, field: impl Into<Ident>, value: &str) -> &mut Self;
+
+    /// Only update the conflicting rows matching the condition
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::InsertBuilder;
+    /// use postgres_querybuilder::prelude::*;
+    ///
+    /// let mut builder = InsertBuilder::new("users");
+    /// builder.fields(vec!["email", "name"]);
+    /// builder.values_row(vec!["rick@example.com", "rick"]);
+    /// builder.on_conflict_constraint("users_email_key");
+    /// builder.do_update_excluded(vec!["name"]);
+    /// builder.do_update_where("users.locked IS FALSE");
+    ///
+    /// assert_eq!(builder.get_query(), "INSERT INTO users (email, name) VALUES ($1, $2) ON CONFLICT ON CONSTRAINT users_email_key DO UPDATE SET name = EXCLUDED.name WHERE users.locked IS FALSE");
+    /// ```
+    fn do_update_where(&mut self, condition: &str) -> &mut Self;
+
+    /// Update the given fields with the values that were proposed for insertion
+    fn do_update_excluded<I: Into<Ident>>(&mut self, fields: Vec<I>) -> &mut Self {
+        for field in fields {
+            let field: Ident = field.into();
+            let value = format!("EXCLUDED.{}", field);
+            self.do_update_set_computed(field, value.as_str());
+        }
+        self
+    }
+
+    /// Update a field with a bound value
+    fn do_update_set<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        value: T,
+    ) -> &mut Self
+    where
+        Self: QueryBuilder,
+    {
+        let index = self.add_param(value);
+        self.do_update_set_computed(field, format!("${}", index).as_str())
+    }
+
+    /// Upsert on a single column, updating the given fields or doing nothing if none are given
+    fn on_conflict<I: Into<Ident>>(
+        &mut self,
+        conflict_field: impl Into<Ident>,
+        update_fields: Vec<I>,
+    ) -> &mut Self {
+        self.on_conflict_columns(vec![conflict_field.into()]);
+        if update_fields.is_empty() {
+            self.do_nothing()
+        } else {
+            self.do_update_excluded(update_fields)
+        }
+    }
+}
+
+pub trait QueryBuilderWithFrom: QueryBuilder {
+    /// Add a raw from item, like `generate_series(1, 10) AS g`
+    #[allow(clippy::wrong_self_convention)]
+    fn from_raw(&mut self, item: &str) -> &mut Self;
+
+    fn from(&mut self, item: impl Into<Ident>) -> &mut Self {
+        let item = self.add_ident(item);
+        self.from_raw(item.as_str())
+    }
+
+    /// Add a subquery as a from item, the alias is raw sql that can list the column names
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::{SelectBuilder, UpdateBuilder};
+    /// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithFrom, QueryBuilderWithGroupBy, QueryBuilderWithSet, QueryBuilderWithWhere};
+    ///
+    /// let mut subquery = SelectBuilder::new("orders");
+    /// subquery.select("user_id");
+    /// subquery.select("sum(total)");
+    /// subquery.where_eq("status", "paid");
+    /// subquery.group_by("user_id");
+    /// let mut builder = UpdateBuilder::new("users");
+    /// builder.set("updated", true);
+    /// builder.set_computed("total", "totals.total");
+    /// builder.from_subquery("totals (user_id, total)", subquery);
+    /// builder.where_condition("totals.user_id = users.id");
+    ///
+    /// assert_eq!(builder.get_query(), "UPDATE users SET updated = $1, total = totals.total FROM (SELECT user_id, sum(total) FROM orders WHERE status = $2 GROUP BY user_id) totals (user_id, total) WHERE totals.user_id = users.id");
+    /// ```
+    #[allow(clippy::wrong_self_convention)]
+    fn from_subquery<Q: QueryBuilder>(&mut self, alias: &str, query: Q) -> &mut Self {
+        let subquery = self.add_subquery(query);
+        self.from_raw(format!("({}) {}", subquery, alias).as_str())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Order {
+    Asc(String),
+    Desc(String),
+}
+
+impl fmt::Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Order::Asc(column) => write!(f, "{} ASC", column),
+            Order::Desc(column) => write!(f, "{} DESC", column),
+        }
+    }
+}
+
+pub trait QueryBuilderWithOrder {
+    fn order_by(&mut self, field: Order);
+
+    /// Order by a sql fragment, `order` is the variant to use
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::{sql, SelectBuilder};
+    /// use postgres_querybuilder::prelude::{Order, QueryBuilder, QueryBuilderWithOrder, QueryBuilderWithWhere};
+    ///
+    /// let mut builder = SelectBuilder::new("users");
+    /// builder.where_eq("active", true);
+    /// builder.order_by_fragment(Order::Desc, sql!("similarity(name, ?)", "rick").unwrap());
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM users WHERE active = $1 ORDER BY similarity(name, $2) DESC");
+    /// ```
+    fn order_by_fragment(&mut self, order: impl FnOnce(String) -> Order, fragment: Fragment)
+    where
+        Self: QueryBuilder,
+    {
+        let field = self.add_fragment(fragment);
+        self.order_by(order(field));
+    }
+}
+
+/// Strength of the lock taken on the selected rows
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockStrength {
+    Update,
+    NoKeyUpdate,
+    Share,
+    KeyShare,
+}
+
+impl fmt::Display for LockStrength {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LockStrength::Update => write!(f, "FOR UPDATE"),
+            LockStrength::NoKeyUpdate => write!(f, "FOR NO KEY UPDATE"),
+            LockStrength::Share => write!(f, "FOR SHARE"),
+            LockStrength::KeyShare => write!(f, "FOR KEY SHARE"),
+        }
+    }
+}
+
+/// Behaviour when a selected row is already locked, waiting by default
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockWait {
+    NoWait,
+    SkipLocked,
+}
+
+impl fmt::Display for LockWait {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LockWait::NoWait => write!(f, "NOWAIT"),
+            LockWait::SkipLocked => write!(f, "SKIP LOCKED"),
+        }
+    }
+}
+
+/// Common table expression to add to a query with `QueryBuilderWithQueries::with`
+pub struct WithQuery {
+    name: String,
+    columns: Vec<String>,
+    materialized: Option<bool>,
+    query: String,
+    /// Parameters of a query built by another builder, raw sql has none
+    params: Option<Bucket>,
+}
+
+impl WithQuery {
+    /// Create a common table expression from raw sql
+    pub fn new(name: impl Into<Ident>, query: &str) -> Self {
+        WithQuery {
+            name: name.into().to_string(),
+            columns: vec![],
+            materialized: None,
+            query: query.into(),
+            params: None,
+        }
+    }
+
+    /// Create a common table expression from a builder, keeping its parameters
+    ///
+    /// An invalid builder makes `try_build` fail on the query the expression is added to.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::{InsertBuilder, SelectBuilder};
+    /// use postgres_querybuilder::prelude::*;
+    ///
+    /// let mut archive = InsertBuilder::new("archives");
+    /// archive.field("name");
+    /// archive.value("rick");
+    /// archive.returning(vec!["id", "name"]);
+    /// let mut builder = SelectBuilder::new("inserted");
+    /// builder.with(WithQuery::from_builder("inserted", archive).columns(vec!["archive_id", "name"]).not_materialized());
+    /// builder.where_ne("name", "morty");
+    ///
+    /// assert_eq!(builder.get_query(), "WITH inserted (archive_id, name) AS NOT MATERIALIZED (INSERT INTO archives (name) VALUES ($1) RETURNING id, name) SELECT * FROM inserted WHERE name <> $2");
+    /// ```
+    pub fn from_builder<Q: QueryBuilder>(name: impl Into<Ident>, query: Q) -> Self {
+        let (sql, params) = take_query(query);
+        let mut result = WithQuery::new(name, sql.as_str());
+        result.params = Some(params);
+        result
+    }
+
+    /// Name the columns of the common table expression
+    pub fn columns<I: Into<Ident>>(mut self, columns: Vec<I>) -> Self {
+        self.columns = columns
+            .into_iter()
+            .map(|column| column.into().to_string())
+            .collect();
+        self
+    }
+
+    /// Force the common table expression to be computed once
+    pub fn materialized(mut self) -> Self {
+        self.materialized = Some(true);
+        self
+    }
+
+    /// Allow the common table expression to be folded into the parent query
+    pub fn not_materialized(mut self) -> Self {
+        self.materialized = Some(false);
+        self
+    }
+
+    /// Merge the parameters in the parent's bucket and render the expression,
+    /// raw sql is kept as is since its placeholders already reference the parent's parameters
+    pub(crate) fn render(self, params: &mut Bucket) -> String {
+        params.check_ident(&self.name);
+        for column in self.columns.iter() {
+            params.check_ident(column);
+        }
+        let query = match self.params {
+            Some(values) => shift_placeholders(&self.query, params.append(values)),
+            None => self.query,
+        };
+        let mut result = self.name;
+        if !self.columns.is_empty() {
+            result.push_str(&format!(" ({})", self.columns.join(", ")));
+        }
+        result.push_str(" AS ");
+        match self.materialized {
+            Some(true) => result.push_str("MATERIALIZED "),
+            Some(false) => result.push_str("NOT MATERIALIZED "),
+            None => (),
+        };
+        result.push_str(&format!("({})", query));
+        result
+    }
+}
+
+pub trait QueryBuilderWithQueries {
+    /// Add a common table expression to the query
+    fn with(&mut self, query: WithQuery) -> &mut Self;
+
+    /// Make the common table expressions able to reference themselves
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::*;
+    ///
+    /// let mut builder = SelectBuilder::new("tree");
+    /// builder.with_recursive();
+    /// builder.with_query("tree", "SELECT id FROM nodes WHERE id = 1 UNION SELECT nodes.id FROM nodes JOIN tree ON nodes.parent_id = tree.id");
+    ///
+    /// assert_eq!(builder.get_query(), "WITH RECURSIVE tree AS (SELECT id FROM nodes WHERE id = 1 UNION SELECT nodes.id FROM nodes JOIN tree ON nodes.parent_id = tree.id) SELECT * FROM tree");
+    /// ```
+    fn with_recursive(&mut self) -> &mut Self;
+
+    fn with_query(&mut self, name: impl Into<Ident>, query: &str) -> &mut Self {
+        self.with(WithQuery::new(name, query))
+    }
+
+    /// Add a common table expression built by another builder, with its parameters
+    fn with_builder<Q: QueryBuilder>(&mut self, name: impl Into<Ident>, query: Q) -> &mut Self {
+        self.with(WithQuery::from_builder(name, query))
+    }
 }
@@ -1,18 +1,32 @@
 use crate::bucket::Bucket;
+use crate::clause::{Clause, Sections};
+use crate::ident::strict_names;
 use crate::prelude::*;
 use postgres_types::ToSql;
 
 pub struct SelectBuilder {
-    with_queries: Vec<(String, String)>,
+    with_queries: Vec<String>,
+    recursive: bool,
+    distinct: bool,
+    distinct_on: Vec<String>,
     columns: Vec<String>,
     from_table: String,
+    from_items: Vec<String>,
     conditions: Vec<String>,
     joins: Vec<Join>,
     groups: Vec<String>,
+    group_distinct: bool,
+    having: Vec<String>,
+    windows: Vec<(String, Window)>,
+    window_copies: Vec<Window>,
+    window_functions: bool,
     order: Vec<Order>,
     limit: Option<String>,
     offset: Option<String>,
+    locks: Vec<(LockStrength, Vec<String>, Option<LockWait>)>,
+    missing_lock: bool,
     params: Bucket,
+    config: BuilderConfig,
 }
 
 impl SelectBuilder {
@@ -25,21 +39,95 @@ impl SelectBuilder {
     ///
     /// let mut builder = SelectBuilder::new("users");
     /// ```
-    pub fn new(from: &str) -> Self {
+    pub fn new(from: impl Into<Ident>) -> Self {
+        SelectBuilder::with_config(from, BuilderConfig::default())
+    }
+
+    /// Create a new select query for a given table with the given settings
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::{sql, SelectBuilder};
+    /// use postgres_querybuilder::prelude::{BuilderConfig, Ident, IdentPolicy, QueryBuildError, QueryBuilder};
+    ///
+    /// let config = BuilderConfig {
+    ///     idents: IdentPolicy::Strict,
+    ///     ..BuilderConfig::default()
+    /// };
+    /// let mut builder = SelectBuilder::with_config("users", config);
+    /// builder.select("id");
+    /// builder.select(Ident::new("Name"));
+    /// builder.select_fragment(sql!("count(*) OVER ()").unwrap());
+    ///
+    /// assert_eq!(builder.try_build().unwrap().query, r#"SELECT id, "Name", count(*) OVER () FROM users"#);
+    ///
+    /// builder.select("password; DROP TABLE users");
+    ///
+    /// assert_eq!(
+    ///     builder.try_build().err(),
+    ///     Some(QueryBuildError::InvalidIdent { name: "password; DROP TABLE users".into() })
+    /// );
+    /// ```
+    pub fn with_config(from: impl Into<Ident>, config: BuilderConfig) -> Self {
+        let mut params = Bucket::new();
         SelectBuilder {
             with_queries: vec![],
+            recursive: false,
+            distinct: false,
+            distinct_on: vec![],
             columns: vec![],
-            from_table: from.into(),
+            from_table: params.ident(from),
+            from_items: vec![],
             conditions: vec![],
             joins: vec![],
             groups: vec![],
+            group_distinct: false,
+            having: vec![],
+            windows: vec![],
+            window_copies: vec![],
+            window_functions: false,
             order: vec![],
             limit: None,
             offset: None,
-            params: Bucket::new(),
+            locks: vec![],
+            missing_lock: false,
+            params,
+            config,
         }
     }
 
+    /// Create a select builder reading the table and the columns of a model
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::{Model, QueryBuilder, QueryBuilderWithWhere};
+    ///
+    /// struct User;
+    ///
+    /// impl Model for User {
+    ///     const TABLE: &'static str = "users";
+    ///
+    ///     fn columns(_prefix: &str) -> Vec<String> {
+    ///         vec!["users.id".into(), "users.name".into()]
+    ///     }
+    /// }
+    ///
+    /// let mut builder = SelectBuilder::for_model::<User>();
+    /// builder.where_eq("id", 42);
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT users.id, users.name FROM users WHERE id = $1");
+    /// ```
+    pub fn for_model<M: Model>() -> Self {
+        let mut builder = SelectBuilder::new(M::TABLE);
+        for column in M::columns("") {
+            builder.select(column);
+        }
+        builder
+    }
+
     /// Add a column to select
     ///
     /// # Examples
@@ -54,11 +142,289 @@ impl SelectBuilder {
     ///
     /// assert_eq!(builder.get_query(), "SELECT id, email FROM users");
     /// ```
-    pub fn select(&mut self, column: &str) -> &mut Self {
-        self.columns.push(column.to_string());
+    pub fn select(&mut self, column: impl Into<Ident>) -> &mut Self {
+        let column = self.params.ident(column);
+        self.columns.push(column);
+        self
+    }
+
+    /// Add a subquery returning a single value to the selected columns
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithWhere};
+    ///
+    /// let mut subquery = SelectBuilder::new("orders");
+    /// subquery.select("count(*)");
+    /// subquery.where_condition("orders.user_id = users.id");
+    /// subquery.where_eq("status", "paid");
+    /// let mut builder = SelectBuilder::new("users");
+    /// builder.select("id");
+    /// builder.select_subquery("paid_orders", subquery);
+    /// builder.where_eq("active", true);
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT id, (SELECT count(*) FROM orders WHERE orders.user_id = users.id AND status = $1) AS paid_orders FROM users WHERE active = $2");
+    /// ```
+    pub fn select_subquery<Q: QueryBuilder>(
+        &mut self,
+        alias: impl Into<Ident>,
+        query: Q,
+    ) -> &mut Self {
+        let subquery = self.add_subquery(query);
+        let alias = self.params.ident(alias);
+        self.columns.push(format!("({}) AS {}", subquery, alias));
         self
     }
 
+    /// Add a sql fragment to the selected columns
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::{sql, SelectBuilder};
+    /// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithWhere};
+    ///
+    /// let mut builder = SelectBuilder::new("users");
+    /// builder.select("id");
+    /// builder.select_fragment(sql!("coalesce(nickname, ?) AS nickname", "anonymous").unwrap());
+    /// builder.where_eq("active", true);
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT id, coalesce(nickname, $1) AS nickname FROM users WHERE active = $2");
+    /// ```
+    pub fn select_fragment(&mut self, fragment: Fragment) -> &mut Self {
+        let column = self.add_fragment(fragment);
+        self.columns.push(column);
+        self
+    }
+
+    /// Add a window function call to the selected columns
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::{Order, QueryBuilder, Window};
+    ///
+    /// let mut builder = SelectBuilder::new("orders");
+    /// builder.select("id");
+    /// builder.select_over(
+    ///     "rank",
+    ///     "row_number()",
+    ///     Window::new().partition_by("user_id").order_by(Order::Desc("total".into())),
+    /// );
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT id, row_number() OVER (PARTITION BY user_id ORDER BY total DESC) AS rank FROM orders");
+    /// ```
+    pub fn select_over(
+        &mut self,
+        alias: impl Into<Ident>,
+        function: &str,
+        window: Window,
+    ) -> &mut Self {
+        let alias = self.params.ident(alias);
+        self.check_window(&window, !window.is_reference());
+        self.window_functions = true;
+        self.columns
+            .push(format!("{} {} AS {}", function, window.over(), alias));
+        self
+    }
+
+    /// Declare a named window, to use with `Window::named`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::{FrameBound, FrameUnit, Order, QueryBuilder, Window};
+    ///
+    /// let mut builder = SelectBuilder::new("orders");
+    /// builder.window("w", Window::new().partition_by("user_id").order_by(Order::Asc("created_at".into())));
+    /// builder.select_over("position", "row_number()", Window::named("w"));
+    /// builder.select_over(
+    ///     "running_total",
+    ///     "sum(total)",
+    ///     Window::named("w").frame(FrameUnit::Rows, FrameBound::UnboundedPreceding),
+    /// );
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT row_number() OVER w AS position, sum(total) OVER (w ROWS UNBOUNDED PRECEDING) AS running_total FROM orders WINDOW w AS (PARTITION BY user_id ORDER BY created_at ASC)");
+    /// ```
+    pub fn window(&mut self, name: impl Into<Ident>, window: Window) -> &mut Self {
+        let name = self.params.ident(name);
+        self.check_window(&window, true);
+        self.windows.push((name, window));
+        self
+    }
+
+    /// Only return distinct rows
+    ///
+    /// `try_build` fails with `QueryBuildError::DistinctOrderNotSelected` when an
+    /// `ORDER BY` column is missing from selected columns that are all plain
+    /// identifiers, and with `QueryBuildError::ConflictingDistinct` when `distinct_on`
+    /// is also called. Other `ORDER BY` expressions are left for postgres to check.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::QueryBuilder;
+    ///
+    /// let mut builder = SelectBuilder::new("users");
+    /// builder.distinct();
+    /// builder.select("country");
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT DISTINCT country FROM users");
+    /// ```
+    pub fn distinct(&mut self) -> &mut Self {
+        self.distinct = true;
+        self
+    }
+
+    /// Only return the first row of each set of rows with the same expressions
+    ///
+    /// `try_build` fails with `QueryBuildError::DistinctOnOrderMismatch` when
+    /// the expressions don't match the leading `ORDER BY` expressions.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::{Order, QueryBuildError, QueryBuilder, QueryBuilderWithOrder};
+    ///
+    /// let mut builder = SelectBuilder::new("orders");
+    /// builder.distinct_on(vec!["user_id"]);
+    /// builder.order_by(Order::Desc("created_at".into()));
+    ///
+    /// assert_eq!(builder.try_build().err(), Some(QueryBuildError::DistinctOnOrderMismatch));
+    ///
+    /// let mut builder = SelectBuilder::new("orders");
+    /// builder.distinct_on(vec!["user_id"]);
+    /// builder.order_by(Order::Asc("user_id".into()));
+    /// builder.order_by(Order::Desc("created_at".into()));
+    ///
+    /// assert_eq!(builder.try_build().unwrap().query, "SELECT DISTINCT ON (user_id) * FROM orders ORDER BY user_id ASC, created_at DESC");
+    /// ```
+    pub fn distinct_on<I: Into<Ident>>(&mut self, expressions: Vec<I>) -> &mut Self {
+        for expression in expressions {
+            let expression = self.params.ident(expression);
+            self.distinct_on.push(expression);
+        }
+        self
+    }
+
+    /// Lock the selected rows, see the `for_update`, `for_no_key_update`,
+    /// `for_share` and `for_key_share` shortcuts
+    ///
+    /// `try_build` fails with `QueryBuildError::LockingNotAllowed` when the query
+    /// uses `DISTINCT`, `GROUP BY`, `HAVING`, `WINDOW` or a window function added
+    /// with `select_over`. Aggregates in raw columns aren't detected, postgres
+    /// rejects them when the query runs.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::{LockStrength, Order, QueryBuilder, QueryBuilderWithLimit, QueryBuilderWithOrder, QueryBuilderWithWhere};
+    ///
+    /// let mut builder = SelectBuilder::new("jobs");
+    /// builder.where_eq("status", "pending");
+    /// builder.order_by(Order::Asc("created_at".into()));
+    /// builder.limit(10);
+    /// builder.lock(LockStrength::Update).skip_locked();
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM jobs WHERE status = $1 ORDER BY created_at ASC LIMIT $2 FOR UPDATE SKIP LOCKED");
+    /// ```
+    pub fn lock(&mut self, strength: LockStrength) -> &mut Self {
+        self.locks.push((strength, vec![], None));
+        self
+    }
+
+    /// Lock the selected rows against updates and deletes
+    pub fn for_update(&mut self) -> &mut Self {
+        self.lock(LockStrength::Update)
+    }
+
+    /// Lock the selected rows against deletes and updates of their keys
+    pub fn for_no_key_update(&mut self) -> &mut Self {
+        self.lock(LockStrength::NoKeyUpdate)
+    }
+
+    /// Lock the selected rows against updates and deletes, sharing the lock with other readers
+    pub fn for_share(&mut self) -> &mut Self {
+        self.lock(LockStrength::Share)
+    }
+
+    /// Lock the selected rows against deletes and updates of their keys, sharing the lock
+    pub fn for_key_share(&mut self) -> &mut Self {
+        self.lock(LockStrength::KeyShare)
+    }
+
+    /// Restrict the last locking clause to the rows of some tables
+    ///
+    /// `try_build` fails with `QueryBuildError::MissingLock` if no locking clause was added.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::SelectBuilder;
+    /// use postgres_querybuilder::prelude::{QueryBuilder, QueryBuilderWithJoin};
+    ///
+    /// let mut builder = SelectBuilder::new("jobs");
+    /// builder.inner_join("workers", "workers.id = jobs.worker_id");
+    /// builder.for_update().lock_of(vec!["jobs"]).nowait();
+    /// builder.for_key_share().lock_of(vec!["workers"]);
+    ///
+    /// assert_eq!(builder.get_query(), "SELECT * FROM jobs INNER JOIN workers ON workers.id = jobs.worker_id FOR UPDATE OF jobs NOWAIT FOR KEY SHARE OF workers");
+    /// ```
+    pub fn lock_of<I: Into<Ident>>(&mut self, tables: Vec<I>) -> &mut Self {
+        let tables: Vec<String> = tables
+            .into_iter()
+            .map(|table| self.params.ident(table))
+            .collect();
+        if let Some(lock) = self.last_lock() {
+            lock.1.extend(tables);
+        }
+        self
+    }
+
+    /// Fail instead of waiting when a row of the last locking clause is already locked
+    ///
+    /// `try_build` fails with `QueryBuildError::MissingLock` if no locking clause was added.
+    pub fn nowait(&mut self) -> &mut Self {
+        if let Some(lock) = self.last_lock() {
+            lock.2 = Some(LockWait::NoWait);
+        }
+        self
+    }
+
+    /// Leave out the rows of the last locking clause that are already locked
+    ///
+    /// `try_build` fails with `QueryBuildError::MissingLock` if no locking clause was added.
+    pub fn skip_locked(&mut self) -> &mut Self {
+        if let Some(lock) = self.last_lock() {
+            lock.2 = Some(LockWait::SkipLocked);
+        }
+        self
+    }
+
+    /// Last locking clause, remembering for `try_build` when there is none
+    fn last_lock(&mut self) -> Option<&mut (LockStrength, Vec<String>, Option<LockWait>)> {
+        self.missing_lock |= self.locks.is_empty();
+        self.locks.last_mut()
+    }
+
+    /// Check the identifiers of a window and keep it aside when it copies a named
+    /// window, which is only checked by `validate` once every window is declared
+    fn check_window(&mut self, window: &Window, copied: bool) {
+        for ident in window.idents() {
+            self.params.check_ident(ident);
+        }
+        if copied && window.base().is_some() {
+            self.window_copies.push(window.clone());
+        }
+    }
+
     /// Add a raw where condition
     ///
     /// # Examples
@@ -77,40 +443,118 @@ impl SelectBuilder {
         self
     }
 
-    pub fn get_values(&mut self) -> &Vec<Box<(dyn ToSql + Sync + 'static)>> {
+    pub fn get_values(&mut self) -> &Vec<Box<dyn ToSql + Sync + 'static>> {
         &self.params.content
     }
 }
 
 impl SelectBuilder {
     fn with_queries_to_query(&self) -> Option<String> {
-        if self.with_queries.len() > 0 {
-            let result: Vec<String> = self
-                .with_queries
-                .iter()
-                .map(|item| format!("{} AS ({})", item.0, item.1))
-                .collect();
-            Some(format!("WITH {}", result.join(", ")))
+        if !self.with_queries.is_empty() {
+            let keyword = if self.recursive {
+                "WITH RECURSIVE"
+            } else {
+                "WITH"
+            };
+            Some(format!("{} {}", keyword, self.with_queries.join(", ")))
         } else {
             None
         }
     }
 
     fn select_to_query(&self) -> String {
-        let columns = if self.columns.len() == 0 {
+        let columns = if self.columns.is_empty() {
             "*".to_string()
         } else {
             self.columns.join(", ")
         };
-        format!("SELECT {}", columns)
+        if !self.distinct_on.is_empty() {
+            format!(
+                "SELECT DISTINCT ON ({}) {}",
+                self.distinct_on.join(", "),
+                columns
+            )
+        } else if self.distinct {
+            format!("SELECT DISTINCT {}", columns)
+        } else {
+            format!("SELECT {}", columns)
+        }
+    }
+
+    /// Postgres only sorts the rows of a `DISTINCT` query by selected columns,
+    /// returns the first order expression that is certainly not one of them
+    ///
+    /// Only plain identifiers are compared, by their last part or alias. The check
+    /// is skipped when an expression, a `*` or a position is selected, and positions
+    /// or expressions in the `ORDER BY` list are never reported.
+    fn distinct_order_not_selected(&self) -> Option<&str> {
+        if !self.distinct || !self.distinct_on.is_empty() || self.columns.is_empty() {
+            return None;
+        }
+        let plain_names = |item: &str| match item.starts_with(|c: char| c.is_ascii_digit()) {
+            true => None,
+            false => strict_names(item),
+        };
+        let mut names = vec![];
+        for column in self.columns.iter() {
+            for column in split_columns(column) {
+                names.extend(plain_names(column)?);
+            }
+        }
+        self.order
+            .iter()
+            .map(|order| match order {
+                Order::Asc(expression) | Order::Desc(expression) => expression.as_str(),
+            })
+            .find(|expression| match plain_names(expression) {
+                Some(term) => term.len() == 1 && !names.contains(&term[0]),
+                None => false,
+            })
+    }
+
+    /// Postgres sorts by the `DISTINCT ON` expressions first, so they have to lead
+    /// the `ORDER BY` list in any order, unless it is made of some of them only
+    fn distinct_on_matches_order(&self) -> bool {
+        let expressions = &self.distinct_on;
+        if expressions.is_empty() {
+            return true;
+        }
+        let order: Vec<&str> = self
+            .order
+            .iter()
+            .map(|order| match order {
+                Order::Asc(expression) | Order::Desc(expression) => expression.as_str(),
+            })
+            .collect();
+        let leading = order
+            .iter()
+            .take_while(|item| expressions.iter().any(|expression| expression == *item))
+            .count();
+        leading == order.len()
+            || expressions
+                .iter()
+                .all(|expression| order[..leading].contains(&expression.as_str()))
+    }
+
+    fn table_to_query(&self) -> String {
+        if self.from_items.is_empty() {
+            format!("FROM {}", self.from_table)
+        } else {
+            format!("FROM {}, {}", self.from_table, self.from_items.join(", "))
+        }
     }
 
-    fn from_to_query(&self) -> String {
-        format!("FROM {}", self.from_table)
+    fn joins_to_query(&self) -> Option<String> {
+        if !self.joins.is_empty() {
+            let result: Vec<String> = self.joins.iter().map(|join| join.to_string()).collect();
+            Some(result.join(" "))
+        } else {
+            None
+        }
     }
 
     fn where_to_query(&self) -> Option<String> {
-        if self.conditions.len() > 0 {
+        if !self.conditions.is_empty() {
             let result = self.conditions.join(" AND ");
             Some(format!("WHERE {}", result))
         } else {
@@ -119,16 +563,42 @@ impl SelectBuilder {
     }
 
     fn group_by_to_query(&self) -> Option<String> {
-        if self.groups.len() > 0 {
+        if !self.groups.is_empty() {
             let result = self.groups.join(", ");
-            Some(format!("GROUP BY {}", result))
+            let keyword = if self.group_distinct {
+                "GROUP BY DISTINCT"
+            } else {
+                "GROUP BY"
+            };
+            Some(format!("{} {}", keyword, result))
+        } else {
+            None
+        }
+    }
+
+    fn having_to_query(&self) -> Option<String> {
+        if !self.having.is_empty() {
+            Some(format!("HAVING {}", self.having.join(" AND ")))
+        } else {
+            None
+        }
+    }
+
+    fn window_to_query(&self) -> Option<String> {
+        if !self.windows.is_empty() {
+            let windows: Vec<String> = self
+                .windows
+                .iter()
+                .map(|(name, window)| format!("{} AS ({})", name, window))
+                .collect();
+            Some(format!("WINDOW {}", windows.join(", ")))
         } else {
             None
         }
     }
 
     fn order_by_to_query(&self) -> Option<String> {
-        if self.order.len() > 0 {
+        if !self.order.is_empty() {
             let result: Vec<String> = self.order.iter().map(|order| order.to_string()).collect();
             Some(format!("ORDER BY {}", result.join(", ")))
         } else {
@@ -137,17 +607,34 @@ impl SelectBuilder {
     }
 
     fn limit_to_query(&self) -> Option<String> {
-        match self.limit.as_ref() {
-            Some(limit) => Some(format!("LIMIT {}", limit)),
-            None => None,
-        }
+        self.limit.as_ref().map(|limit| format!("LIMIT {}", limit))
     }
 
     fn offset_to_query(&self) -> Option<String> {
-        match self.offset.as_ref() {
-            Some(offset) => Some(format!("OFFSET {}", offset)),
-            None => None,
+        self.offset
+            .as_ref()
+            .map(|offset| format!("OFFSET {}", offset))
+    }
+
+    fn locks_to_query(&self) -> Option<String> {
+        if self.locks.is_empty() {
+            return None;
         }
+        let result: Vec<String> = self
+            .locks
+            .iter()
+            .map(|(strength, tables, wait)| {
+                let mut result = strength.to_string();
+                if !tables.is_empty() {
+                    result.push_str(&format!(" OF {}", tables.join(", ")));
+                }
+                if let Some(wait) = wait {
+                    result.push_str(&format!(" {}", wait));
+                }
+                result
+            })
+            .collect();
+        Some(result.join(" "))
     }
 }
 
@@ -156,40 +643,75 @@ impl QueryBuilder for SelectBuilder {
         self.params.push(value)
     }
 
+    fn append_params(&mut self, params: Bucket) -> usize {
+        self.params.append(params)
+    }
+
+    fn params_len(&self) -> usize {
+        self.params.len()
+    }
+
     fn get_query(&self) -> String {
-        let mut sections: Vec<String> = vec![];
-        match self.with_queries_to_query() {
-            Some(value) => sections.push(value),
-            None => (),
-        };
-        sections.push(self.select_to_query());
-        sections.push(self.from_to_query());
-        match self.where_to_query() {
-            Some(value) => sections.push(value),
-            None => (),
-        };
-        match self.group_by_to_query() {
-            Some(value) => sections.push(value),
-            None => (),
-        };
-        match self.order_by_to_query() {
-            Some(value) => sections.push(value),
-            None => (),
-        };
-        match self.limit_to_query() {
-            Some(value) => sections.push(value),
-            None => (),
-        };
-        match self.offset_to_query() {
-            Some(value) => sections.push(value),
-            None => (),
-        };
-        sections.join(" ")
+        let mut sections = Sections::new();
+        sections.push(Clause::With, self.with_queries_to_query());
+        sections.push(Clause::Target, self.select_to_query());
+        sections.push(Clause::From, self.table_to_query());
+        sections.push(Clause::Join, self.joins_to_query());
+        sections.push(Clause::Where, self.where_to_query());
+        sections.push(Clause::GroupBy, self.group_by_to_query());
+        sections.push(Clause::Having, self.having_to_query());
+        sections.push(Clause::Window, self.window_to_query());
+        sections.push(Clause::OrderBy, self.order_by_to_query());
+        sections.push(Clause::Limit, self.limit_to_query());
+        sections.push(Clause::Offset, self.offset_to_query());
+        sections.push(Clause::Locking, self.locks_to_query());
+        sections.render()
     }
 
-    fn get_ref_params(self) -> Vec<&'static (dyn ToSql + Sync)> {
+    fn get_ref_params(&self) -> Vec<&(dyn ToSql + Sync)> {
         self.params.get_refs()
     }
+
+    fn into_params(self) -> Bucket {
+        self.params
+    }
+
+    fn validate(&self) -> Result<(), QueryBuildError> {
+        self.config.check_idents(&self.params)?;
+        self.params.check_subqueries()?;
+        if self.distinct && !self.distinct_on.is_empty() {
+            return Err(QueryBuildError::ConflictingDistinct);
+        }
+        if !self.distinct_on_matches_order() {
+            return Err(QueryBuildError::DistinctOnOrderMismatch);
+        }
+        for window in self.window_copies.iter() {
+            let base = self
+                .windows
+                .iter()
+                .find(|(name, _)| Some(name) == window.base())
+                .map(|(_, base)| base);
+            window.check_copy(base)?;
+        }
+        if let Some(expression) = self.distinct_order_not_selected() {
+            return Err(QueryBuildError::DistinctOrderNotSelected {
+                expression: expression.to_string(),
+            });
+        }
+        let grouped = self.distinct
+            || !self.distinct_on.is_empty()
+            || !self.groups.is_empty()
+            || !self.having.is_empty()
+            || !self.windows.is_empty()
+            || self.window_functions;
+        if self.missing_lock {
+            return Err(QueryBuildError::MissingLock);
+        }
+        if !self.locks.is_empty() && grouped {
+            return Err(QueryBuildError::LockingNotAllowed);
+        }
+        Ok(())
+    }
 }
 
 impl QueryBuilderWithWhere for SelectBuilder {
@@ -216,30 +738,31 @@ impl QueryBuilderWithOffset for SelectBuilder {
 }
 
 impl QueryBuilderWithJoin for SelectBuilder {
-    fn inner_join(&mut self, table_name: &str, relation: &str) -> &mut Self {
-        self.joins
-            .push(Join::Inner(table_name.to_string(), relation.to_string()));
+    fn join(&mut self, join: Join) -> &mut Self {
+        self.joins.push(join);
         self
     }
+}
 
-    fn left_join(&mut self, table_name: &str, relation: &str) -> &mut Self {
-        self.joins.push(Join::LeftOuter(
-            table_name.to_string(),
-            relation.to_string(),
-        ));
+impl QueryBuilderWithGroupBy for SelectBuilder {
+    fn group_by_element(&mut self, element: impl Into<GroupingElement>) -> &mut Self {
+        let element = element.into();
+        for expression in element.items() {
+            self.params.check_ident(expression);
+        }
+        self.groups.push(element.to_string());
         self
     }
 
-    fn left_outer_join(&mut self, table_name: &str, relation: &str) -> &mut Self {
-        self.joins
-            .push(Join::Left(table_name.to_string(), relation.to_string()));
+    fn group_by_distinct(&mut self) -> &mut Self {
+        self.group_distinct = true;
         self
     }
 }
 
-impl QueryBuilderWithGroupBy for SelectBuilder {
-    fn group_by(&mut self, field: &str) -> &mut Self {
-        self.groups.push(field.to_string());
+impl QueryBuilderWithHaving for SelectBuilder {
+    fn having_condition(&mut self, raw: &str) -> &mut Self {
+        self.having.push(raw.to_string());
         self
     }
 }
@@ -265,16 +788,55 @@ impl QueryBuilderWithOrder for SelectBuilder {
     }
 }
 
+impl QueryBuilderWithFrom for SelectBuilder {
+    fn from_raw(&mut self, item: &str) -> &mut Self {
+        self.from_items.push(item.to_string());
+        self
+    }
+}
+
 impl QueryBuilderWithQueries for SelectBuilder {
-    fn with_query(&mut self, name: &str, query: &str) -> &mut Self {
-        self.with_queries.push((name.into(), query.into()));
+    fn with(&mut self, query: WithQuery) -> &mut Self {
+        let item = query.render(&mut self.params);
+        self.with_queries.push(item);
         self
     }
+
+    fn with_recursive(&mut self) -> &mut Self {
+        self.recursive = true;
+        self
+    }
+}
+
+/// Split a raw select item on the commas that aren't in parentheses or quotes
+fn split_columns(column: &str) -> Vec<&str> {
+    let mut items = vec![];
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    for (index, character) in column.char_indices() {
+        match (quote, character) {
+            (Some(current), _) if current == character => quote = None,
+            (Some(_), _) => (),
+            (None, '\'' | '"') => quote = Some(character),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                items.push(column[start..index].trim());
+                start = index + 1;
+            }
+            _ => (),
+        }
+    }
+    items.push(column[start..].trim());
+    items
 }
 
 #[cfg(test)]
 pub mod test {
     use super::*;
+    use crate::sql;
+    use crate::{InsertBuilder, UpdateBuilder};
 
     #[test]
     fn from_scratch() {
@@ -282,6 +844,70 @@ pub mod test {
         assert_eq!(builder.get_query(), "SELECT * FROM publishers");
     }
 
+    #[test]
+    fn clause_combinations() {
+        for mask in 0..512 {
+            let enabled = |index: usize| mask & (1 << index) != 0;
+            let mut builder = SelectBuilder::new("publishers");
+            let mut expected = vec![];
+            if enabled(0) {
+                builder.offset(5);
+            }
+            if enabled(1) {
+                builder.limit(10);
+            }
+            if enabled(2) {
+                builder.order_by(Order::Asc("id".into()));
+            }
+            if enabled(3) {
+                builder.group_by("id");
+            }
+            if enabled(4) {
+                builder.where_condition("id > 1");
+            }
+            if enabled(5) {
+                builder.inner_join("articles", "articles.publisher_id = publishers.id");
+            }
+            if enabled(6) {
+                builder.select("id");
+            }
+            if enabled(8) {
+                builder.having_condition("count(*) > 1");
+            }
+            if enabled(7) {
+                builder.with_query("tmp", "SELECT 1");
+                expected.push("WITH tmp AS (SELECT 1)".to_string());
+            }
+            expected.push(if enabled(6) { "SELECT id" } else { "SELECT *" }.to_string());
+            expected.push("FROM publishers".to_string());
+            if enabled(5) {
+                expected.push(
+                    "INNER JOIN articles ON articles.publisher_id = publishers.id".to_string(),
+                );
+            }
+            if enabled(4) {
+                expected.push("WHERE id > 1".to_string());
+            }
+            if enabled(3) {
+                expected.push("GROUP BY id".to_string());
+            }
+            if enabled(8) {
+                expected.push("HAVING count(*) > 1".to_string());
+            }
+            if enabled(2) {
+                expected.push("ORDER BY id ASC".to_string());
+            }
+            let offset_index = if enabled(0) { 1 } else { 0 };
+            if enabled(1) {
+                expected.push(format!("LIMIT ${}", offset_index + 1));
+            }
+            if enabled(0) {
+                expected.push("OFFSET $1".to_string());
+            }
+            assert_eq!(builder.get_query(), expected.join(" "));
+        }
+    }
+
     #[test]
     fn with_columns() {
         let mut builder = SelectBuilder::new("publishers");
@@ -310,6 +936,26 @@ pub mod test {
         );
     }
 
+    #[test]
+    fn with_joins() {
+        let mut builder = SelectBuilder::new("publishers AS p");
+        builder.select("p.id");
+        builder.inner_join("articles AS a", "a.publisher_id = p.id");
+        builder.left_join("authors", "authors.id = a.author_id");
+        builder.left_outer_join("editors", "editors.id = p.editor_id");
+        builder.right_join("countries", "countries.id = p.country_id");
+        builder.full_outer_join("offices", "offices.publisher_id = p.id");
+        builder.cross_join("settings");
+        builder.natural_join("stats");
+        builder.inner_join_using("owners", vec!["owner_id"]);
+        builder.left_join_using("tags", vec!["tag_id", "lang"]);
+        builder.where_eq("p.id", 42);
+        assert_eq!(
+            builder.get_query(),
+            "SELECT p.id FROM publishers AS p INNER JOIN articles AS a ON a.publisher_id = p.id LEFT JOIN authors ON authors.id = a.author_id LEFT OUTER JOIN editors ON editors.id = p.editor_id RIGHT JOIN countries ON countries.id = p.country_id FULL OUTER JOIN offices ON offices.publisher_id = p.id CROSS JOIN settings NATURAL JOIN stats INNER JOIN owners USING (owner_id) LEFT JOIN tags USING (tag_id, lang) WHERE p.id = $1"
+        );
+    }
+
     #[test]
     fn with_where_eq() {
         let mut builder = SelectBuilder::new("publishers");
@@ -325,6 +971,145 @@ pub mod test {
         );
     }
 
+    #[test]
+    fn with_where_comparisons() {
+        let mut builder = SelectBuilder::new("publishers");
+        builder.where_lt("a", 1);
+        builder.where_lte("b", 2);
+        builder.where_gt("c", 3);
+        builder.where_gte("d", 4);
+        builder.where_between("e", 5, 6);
+        builder.where_like("f", "a%");
+        builder.where_ilike("g", "b%");
+        builder.where_not_like("h", "c%");
+        builder.where_similar_to("i", "(d|e)%");
+        builder.where_regex("j", "^f");
+        builder.where_iregex("k", "^g");
+        builder.where_is_distinct_from("l", 7);
+        builder.where_is_null("m");
+        builder.where_is_not_null("n");
+        builder.where_is_true("o");
+        builder.where_is_false("p");
+        assert_eq!(
+            builder.get_query(),
+            "SELECT * FROM publishers WHERE a < $1 AND b <= $2 AND c > $3 AND d >= $4 AND e BETWEEN $5 AND $6 AND f LIKE $7 AND g ILIKE $8 AND h NOT LIKE $9 AND i SIMILAR TO $10 AND j ~ $11 AND k ~* $12 AND l IS DISTINCT FROM $13 AND m IS NULL AND n IS NOT NULL AND o IS TRUE AND p IS FALSE"
+        );
+        assert_eq!(builder.get_ref_params().len(), 13);
+    }
+
+    #[test]
+    fn with_where_in() {
+        let mut builder = SelectBuilder::new("publishers");
+        builder.where_in("id", vec![1, 2]);
+        builder.where_not_in::<i32>("id", vec![]);
+        builder.where_in_list("name", vec!["a", "b"]);
+        builder.where_not_in_list("name", vec!["c"]);
+        builder.where_not_in_list::<&str>("alias", vec![]);
+        assert_eq!(
+            builder.get_query(),
+            "SELECT * FROM publishers WHERE id = ANY($1) AND id <> ALL($2) AND name IN ($3, $4) AND name NOT IN ($5) AND TRUE"
+        );
+        assert_eq!(builder.get_ref_params().len(), 5);
+    }
+
+    #[test]
+    fn with_builder_subquery() {
+        let mut counts = SelectBuilder::new("articles");
+        counts.select("publisher_id, count(*)");
+        counts.where_eq("draft", false);
+        counts.group_by("publisher_id");
+        let mut builder = SelectBuilder::new("publishers_count");
+        builder.where_gt("count", 10);
+        builder.with_recursive();
+        builder.with(
+            WithQuery::from_builder("publishers_count", counts)
+                .columns(vec!["publisher_id", "count"])
+                .materialized(),
+        );
+        builder.with_query("publishers_subquery", "SELECT * FROM publishers");
+        assert_eq!(
+            builder.get_query(),
+            "WITH RECURSIVE publishers_count (publisher_id, count) AS MATERIALIZED (SELECT publisher_id, count(*) FROM articles WHERE draft = $2 GROUP BY publisher_id), publishers_subquery AS (SELECT * FROM publishers) SELECT * FROM publishers_count WHERE count > $1"
+        );
+        assert_eq!(builder.get_ref_params().len(), 2);
+    }
+
+    #[test]
+    fn invalid_subqueries() {
+        let mut archive = UpdateBuilder::new("articles");
+        archive.where_eq("draft", true);
+        archive.returning(vec!["id"]);
+        let mut builder = SelectBuilder::new("archived");
+        builder.with_builder("archived", archive);
+        assert_eq!(builder.try_build().err(), Some(QueryBuildError::MissingSet));
+
+        let mut subquery = InsertBuilder::new("logs");
+        subquery.fields(vec!["kind", "message"]);
+        subquery.values_row(vec!["read"]);
+        let mut builder = SelectBuilder::new("publishers");
+        builder.where_exists(subquery);
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::ValuesCountMismatch {
+                row: 0,
+                expected: 2,
+                found: 1
+            })
+        );
+
+        let mut subquery = SelectBuilder::new("articles");
+        subquery.select("publisher_id");
+        subquery.where_eq("draft", false);
+        let mut builder = SelectBuilder::new("publishers");
+        builder.where_in_subquery("id", subquery);
+        assert!(builder.try_build().is_ok());
+    }
+
+    #[test]
+    fn with_query_after_params() {
+        let mut builder = SelectBuilder::new("x");
+        builder.where_eq("active", true);
+        let index = builder.add_param(5);
+        builder.with_query("x", &format!("SELECT ${}::int AS v", index));
+        assert_eq!(
+            builder.get_query(),
+            "WITH x AS (SELECT $2::int AS v) SELECT * FROM x WHERE active = $1"
+        );
+        assert_eq!(builder.get_ref_params().len(), 2);
+    }
+
+    #[test]
+    fn with_subqueries() {
+        let mut scalar = SelectBuilder::new("articles");
+        scalar.select("count(*)");
+        scalar.where_condition("articles.publisher_id = publishers.id");
+        scalar.where_eq("draft", false);
+        let mut in_subquery = SelectBuilder::new("owners");
+        in_subquery.select("publisher_id");
+        in_subquery.where_eq("name", "rick");
+        in_subquery.limit(3);
+        let mut exists = SelectBuilder::new("awards");
+        exists.where_condition("awards.publisher_id = publishers.id");
+        exists.where_gt("year", 2000);
+        let mut from = SelectBuilder::new("countries");
+        from.select("id");
+        from.where_eq("code", "FR");
+
+        let mut builder = SelectBuilder::new("publishers");
+        builder.select("id");
+        builder.select_subquery("articles_count", scalar);
+        builder.where_eq("active", true);
+        builder.where_in_subquery("id", in_subquery);
+        builder.where_not_exists(exists);
+        builder.from_subquery("c", from);
+        builder.where_condition("c.id = publishers.country_id");
+        assert_eq!(
+            builder.get_query(),
+            "SELECT id, (SELECT count(*) FROM articles WHERE articles.publisher_id = publishers.id AND draft = $1) AS articles_count FROM publishers, (SELECT id FROM countries WHERE code = $6) c WHERE active = $2 AND id IN (SELECT publisher_id FROM owners WHERE name = $3 LIMIT $4) AND NOT EXISTS (SELECT * FROM awards WHERE awards.publisher_id = publishers.id AND year > $5) AND c.id = publishers.country_id"
+        );
+        assert_eq!(builder.get_ref_params().len(), 6);
+    }
+
     #[test]
     fn with_order() {
         let mut builder = SelectBuilder::new("publishers");
@@ -337,6 +1122,275 @@ pub mod test {
         );
     }
 
+    #[test]
+    fn with_grouping_elements() {
+        let mut builder = SelectBuilder::new("sales");
+        builder.select("brand, size, sum(total)");
+        builder.group_by_distinct();
+        builder.group_by("region");
+        builder.group_by_element(GroupingElement::Cube(vec![
+            GroupingElement::from("brand"),
+            GroupingElement::set(vec!["size", "color"]),
+        ]));
+        builder.group_by_element(GroupingElement::grouping_sets(vec![
+            GroupingElement::set(vec!["year"]),
+            GroupingElement::rollup(vec!["year", "month"]),
+            GroupingElement::set(Vec::<&str>::new()),
+        ]));
+        assert_eq!(
+            builder.get_query(),
+            "SELECT brand, size, sum(total) FROM sales GROUP BY DISTINCT region, CUBE (brand, (size, color)), GROUPING SETS ((year), ROLLUP (year, month), ())"
+        );
+    }
+
+    #[test]
+    fn with_windows() {
+        let mut builder = SelectBuilder::new("articles");
+        builder.select("publisher_id");
+        builder.select_over("articles_rank", "rank()", Window::named("by_count"));
+        builder.select_over(
+            "previous_count",
+            "lag(count(*))",
+            Window::named("by_count").frame_between(
+                FrameUnit::Rows,
+                FrameBound::Preceding("1".into()),
+                FrameBound::Following("1".into()),
+            ),
+        );
+        builder.where_eq("draft", false);
+        builder.group_by("publisher_id");
+        builder.having_gt("count(*)", 1i64);
+        builder.window(
+            "by_count",
+            Window::new().order_by(Order::Desc("count(*)".into())),
+        );
+        builder.order_by(Order::Asc("publisher_id".into()));
+        assert_eq!(
+            builder.get_query(),
+            "SELECT publisher_id, rank() OVER by_count AS articles_rank, lag(count(*)) OVER (by_count ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING) AS previous_count FROM articles WHERE draft = $1 GROUP BY publisher_id HAVING count(*) > $2 WINDOW by_count AS (ORDER BY count(*) DESC) ORDER BY publisher_id ASC"
+        );
+        assert!(builder.try_build().is_ok());
+        builder.select_over(
+            "publisher_rank",
+            "rank()",
+            Window::named("by_count").partition_by("publisher_id"),
+        );
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::WindowPartitionOverride {
+                name: "by_count".into()
+            })
+        );
+    }
+
+    #[test]
+    fn with_window_copies() {
+        let mut builder = SelectBuilder::new("orders");
+        builder.window("by_user", Window::new().partition_by("user_id"));
+        builder.window(
+            "by_date",
+            Window::named("by_user").order_by(Order::Asc("created_at".into())),
+        );
+        builder.window(
+            "running",
+            Window::named("by_date").frame(FrameUnit::Rows, FrameBound::UnboundedPreceding),
+        );
+        builder.select_over("running_total", "sum(total)", Window::named("running"));
+        builder.select_over(
+            "position",
+            "row_number()",
+            Window::named("by_user").order_by(Order::Desc("total".into())),
+        );
+        assert_eq!(
+            builder.try_build().unwrap().query,
+            "SELECT sum(total) OVER running AS running_total, row_number() OVER (by_user ORDER BY total DESC) AS position FROM orders WINDOW by_user AS (PARTITION BY user_id), by_date AS (by_user ORDER BY created_at ASC), running AS (by_date ROWS UNBOUNDED PRECEDING)"
+        );
+        builder.select_over(
+            "previous",
+            "lag(total)",
+            Window::named("by_date").order_by(Order::Desc("total".into())),
+        );
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::WindowOrderOverride {
+                name: "by_date".into()
+            })
+        );
+
+        let mut builder = SelectBuilder::new("orders");
+        builder.window(
+            "running",
+            Window::new().frame(FrameUnit::Rows, FrameBound::UnboundedPreceding),
+        );
+        builder.select_over("running_total", "sum(total)", Window::named("running"));
+        assert!(builder.try_build().is_ok());
+        builder.select_over(
+            "running_max",
+            "max(total)",
+            Window::named("running").order_by(Order::Asc("created_at".into())),
+        );
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::WindowFrameCopy {
+                name: "running".into()
+            })
+        );
+    }
+
+    #[test]
+    fn with_locking() {
+        let mut builder = SelectBuilder::new("jobs");
+        builder.select("id");
+        builder.where_eq("queue", "mail");
+        builder.limit(1);
+        builder.offset(2);
+        builder.for_no_key_update().skip_locked();
+        builder
+            .for_share()
+            .lock_of(vec!["queues", "workers"])
+            .nowait();
+        assert_eq!(
+            builder.try_build().unwrap().query,
+            "SELECT id FROM jobs WHERE queue = $1 LIMIT $2 OFFSET $3 FOR NO KEY UPDATE SKIP LOCKED FOR SHARE OF queues, workers NOWAIT"
+        );
+        builder.group_by("id");
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::LockingNotAllowed)
+        );
+
+        let mut builder = SelectBuilder::new("jobs");
+        builder.distinct();
+        assert!(builder.try_build().is_ok());
+        builder.for_key_share();
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::LockingNotAllowed)
+        );
+
+        let mut builder = SelectBuilder::new("jobs");
+        builder.select_over("position", "row_number()", Window::new());
+        builder.for_update();
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::LockingNotAllowed)
+        );
+    }
+
+    #[test]
+    fn lock_options_without_lock() {
+        let mut builder = SelectBuilder::new("jobs");
+        builder.skip_locked();
+        builder.for_update().nowait();
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::MissingLock)
+        );
+
+        let mut builder = SelectBuilder::new("jobs");
+        builder.lock_of(vec!["jobs"]);
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::MissingLock)
+        );
+        assert_eq!(builder.get_query(), "SELECT * FROM jobs");
+    }
+
+    #[test]
+    fn with_having() {
+        let mut builder = SelectBuilder::new("articles");
+        builder.select("publisher_id");
+        builder.where_eq("draft", false);
+        builder.group_by("publisher_id");
+        builder.having_gt("count(*)", 10i64);
+        builder.having_expr(
+            Condition::eq("max(category)", "news").or(Condition::raw("bool_or(featured)")),
+        );
+        builder.having_fragment(sql!("min(created_at) > now() - ?::interval", "1 year").unwrap());
+        builder.limit(5);
+        assert_eq!(
+            builder.get_query(),
+            "SELECT publisher_id FROM articles WHERE draft = $1 GROUP BY publisher_id HAVING count(*) > $2 AND (max(category) = $3 OR (bool_or(featured))) AND min(created_at) > now() - $4::interval LIMIT $5"
+        );
+        assert_eq!(builder.get_ref_params().len(), 5);
+    }
+
+    #[test]
+    fn with_having_strict_idents() {
+        let config = BuilderConfig {
+            idents: IdentPolicy::Strict,
+            ..BuilderConfig::default()
+        };
+        let mut builder = SelectBuilder::with_config("articles", config);
+        builder.select("publisher_id");
+        builder.group_by("publisher_id");
+        builder.having_gt("count(*)", 10i64);
+        builder.having_operator("array_agg(category)", "@>", vec!["news".to_string()]);
+        builder.having_expr(Condition::raw("bool_or(featured)"));
+        assert_eq!(
+            builder.try_build().unwrap().query,
+            "SELECT publisher_id FROM articles GROUP BY publisher_id HAVING count(*) > $1 AND array_agg(category) @> $2 AND (bool_or(featured))"
+        );
+    }
+
+    #[test]
+    fn with_distinct() {
+        let mut builder = SelectBuilder::new("publishers");
+        builder.distinct_on(vec!["country_id", "city"]);
+        builder.select("id");
+        assert!(builder.try_build().is_ok());
+        builder.order_by(Order::Asc("city".into()));
+        assert!(builder.try_build().is_ok());
+        builder.order_by(Order::Desc("founded_at".into()));
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::DistinctOnOrderMismatch)
+        );
+
+        let mut builder = SelectBuilder::new("publishers");
+        builder.distinct_on(vec!["country_id", "city"]);
+        builder.order_by(Order::Desc("city".into()));
+        builder.order_by(Order::Asc("country_id".into()));
+        builder.order_by(Order::Desc("founded_at".into()));
+        assert_eq!(
+            builder.try_build().unwrap().query,
+            "SELECT DISTINCT ON (country_id, city) * FROM publishers ORDER BY city DESC, country_id ASC, founded_at DESC"
+        );
+
+        let mut builder = SelectBuilder::new("publishers");
+        builder.distinct_on(vec!["country_id"]).distinct();
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::ConflictingDistinct)
+        );
+
+        let mut builder = SelectBuilder::new("publishers");
+        builder.distinct();
+        builder.select("publishers.country_id");
+        builder.select("city AS town, name");
+        builder.order_by(Order::Asc("country_id".into()));
+        builder.order_by(Order::Desc("town".into()));
+        builder.order_by(Order::Asc("publishers.city".into()));
+        builder.order_by(Order::Asc("1".into()));
+        builder.order_by(Order::Asc("lower(name)".into()));
+        assert!(builder.try_build().is_ok());
+        builder.order_by(Order::Asc("p.founded_at".into()));
+        assert_eq!(
+            builder.try_build().err(),
+            Some(QueryBuildError::DistinctOrderNotSelected {
+                expression: "p.founded_at".into()
+            })
+        );
+        builder.select("lower(founded_at::text)");
+        assert!(builder.try_build().is_ok());
+
+        let mut builder = SelectBuilder::new("publishers");
+        builder.distinct();
+        builder.select("publishers.*");
+        builder.order_by(Order::Asc("name".into()));
+        assert!(builder.try_build().is_ok());
+    }
+
     #[test]
     fn with_subquery() {
         let mut builder = SelectBuilder::new("publishers_view");
@@ -350,4 +1404,70 @@ pub mod test {
       "WITH publishers_count AS (SELECT publisher_id, count(*) FROM articles GROUP BY publisher_id), publishers_subquery AS (SELECT * FROM publishers) SELECT * FROM publishers_view"
     );
     }
+
+    #[test]
+    fn strict_idents() {
+        let config = BuilderConfig {
+            idents: IdentPolicy::Strict,
+            ..BuilderConfig::default()
+        };
+        let invalid = |name: &str| {
+            Some(QueryBuildError::InvalidIdent {
+                name: name.to_string(),
+            })
+        };
+
+        let mut builder = SelectBuilder::with_config("publishers", config);
+        builder.select(Ident::new("Name"));
+        builder.where_expr(Condition::eq("country_id", 1).or(Condition::raw("a OR b")));
+        builder.group_by_element(GroupingElement::rollup(vec!["country_id", "city"]));
+        builder.window("w", Window::new().partition_by("city"));
+        builder.select_over("position", "row_number()", Window::named("w"));
+        assert!(builder.try_build().is_ok());
+
+        let mut builder = SelectBuilder::with_config("publishers p", config);
+        assert_eq!(builder.try_build().err(), invalid("publishers p"));
+        assert_eq!(
+            SelectBuilder::new("publishers p")
+                .try_build()
+                .unwrap()
+                .query,
+            "SELECT * FROM publishers p"
+        );
+        builder = SelectBuilder::with_config(Ident::raw("publishers").alias("p"), config);
+        builder.where_eq("lower(name)", "rick");
+        assert_eq!(builder.try_build().err(), invalid("lower(name)"));
+
+        let mut builder = SelectBuilder::with_config("publishers", config);
+        builder.where_expr(Condition::ne("Name", "rick"));
+        assert_eq!(builder.try_build().err(), invalid("Name"));
+
+        let mut builder = SelectBuilder::with_config("publishers", config);
+        let mut subquery = SelectBuilder::new("articles");
+        subquery.select("count(*)");
+        builder.select_subquery("articles_count", subquery);
+        assert_eq!(builder.try_build().err(), invalid("count(*)"));
+
+        let mut builder = SelectBuilder::with_config("publishers", config);
+        builder.group_by_element(GroupingElement::cube(vec![
+            "city",
+            "extract(year from founded_at)",
+        ]));
+        assert_eq!(
+            builder.try_build().err(),
+            invalid("extract(year from founded_at)")
+        );
+
+        let mut builder = SelectBuilder::with_config("publishers", config);
+        builder.select_over(
+            "position",
+            "row_number()",
+            Window::new().partition_by("city, name"),
+        );
+        assert_eq!(builder.try_build().err(), invalid("city, name"));
+
+        let mut builder = SelectBuilder::with_config("publishers", config);
+        builder.with(WithQuery::new("recent", "SELECT 1").columns(vec!["Id"]));
+        assert_eq!(builder.try_build().err(), invalid("Id"));
+    }
 }
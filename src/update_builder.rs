@@ -1,15 +1,18 @@
 use crate::bucket::Bucket;
+use crate::clause::{Clause, Sections};
 use crate::prelude::*;
 use postgres_types::ToSql;
 
 pub struct UpdateBuilder {
-    with_queries: Vec<(String, String)>,
+    with_queries: Vec<String>,
+    recursive: bool,
     table: String,
     fields: Vec<String>,
     returning_fields: Vec<String>,
     from_items: Vec<String>,
     conditions: Vec<String>,
     params: Bucket,
+    config: BuilderConfig,
 }
 
 impl UpdateBuilder {
@@ -28,32 +31,40 @@ impl UpdateBuilder {
     ///
     /// assert_eq!(builder.get_query(), "UPDATE users SET username = $1 WHERE id = $2");
     /// ```
-    pub fn new(from: &str) -> Self {
+    pub fn new(from: impl Into<Ident>) -> Self {
+        UpdateBuilder::with_config(from, BuilderConfig::default())
+    }
+
+    /// Create a new update builder for a given table with the given settings
+    pub fn with_config(from: impl Into<Ident>, config: BuilderConfig) -> Self {
+        let mut params = Bucket::new();
         UpdateBuilder {
             with_queries: vec![],
-            table: from.into(),
+            recursive: false,
+            table: params.ident(from),
             fields: vec![],
             from_items: vec![],
             returning_fields: vec![],
             conditions: vec![],
-            params: Bucket::new(),
+            params,
+            config,
         }
     }
 
-    pub fn get_values(&mut self) -> &Vec<Box<(dyn ToSql + Sync + 'static)>> {
+    pub fn get_values(&mut self) -> &Vec<Box<dyn ToSql + Sync + 'static>> {
         &self.params.content
     }
 }
 
 impl UpdateBuilder {
     fn with_queries_to_query(&self) -> Option<String> {
-        if self.with_queries.len() > 0 {
-            let result: Vec<String> = self
-                .with_queries
-                .iter()
-                .map(|item| format!("{} AS ({})", item.0, item.1))
-                .collect();
-            Some(format!("WITH {}", result.join(", ")))
+        if !self.with_queries.is_empty() {
+            let keyword = if self.recursive {
+                "WITH RECURSIVE"
+            } else {
+                "WITH"
+            };
+            Some(format!("{} {}", keyword, self.with_queries.join(", ")))
         } else {
             None
         }
@@ -64,7 +75,7 @@ impl UpdateBuilder {
     }
 
     fn set_to_query(&self) -> Option<String> {
-        if self.fields.len() > 0 {
+        if !self.fields.is_empty() {
             let fields_query = self.fields.join(", ");
             Some(format!("SET {}", fields_query))
         } else {
@@ -72,8 +83,8 @@ impl UpdateBuilder {
         }
     }
 
-    fn from_items_to_query(&self) -> Option<String> {
-        if self.from_items.len() > 0 {
+    fn items_to_query(&self) -> Option<String> {
+        if !self.from_items.is_empty() {
             let from_items_query = self.from_items.join(", ");
             Some(format!("FROM {}", from_items_query))
         } else {
@@ -82,7 +93,7 @@ impl UpdateBuilder {
     }
 
     fn returning_fields_to_query(&self) -> Option<String> {
-        if self.returning_fields.len() > 0 {
+        if !self.returning_fields.is_empty() {
             let returning_query = self.returning_fields.join(", ");
             Some(format!("RETURNING {}", returning_query))
         } else {
@@ -91,9 +102,11 @@ impl UpdateBuilder {
     }
 
     fn where_to_query(&self) -> Option<String> {
-        if self.conditions.len() > 0 {
+        if !self.conditions.is_empty() {
             let where_query = self.conditions.join(" AND ");
             Some(format!("WHERE {}", where_query))
+        } else if self.config.full_table == FullTablePolicy::Deny {
+            Some("WHERE false".to_string())
         } else {
             None
         }
@@ -105,35 +118,51 @@ impl QueryBuilder for UpdateBuilder {
         self.params.push(value)
     }
 
+    fn append_params(&mut self, params: Bucket) -> usize {
+        self.params.append(params)
+    }
+
+    fn params_len(&self) -> usize {
+        self.params.len()
+    }
+
     fn get_query(&self) -> String {
-        let mut result: Vec<String> = vec![];
-        match self.with_queries_to_query() {
-            Some(value) => result.push(value),
-            None => (),
-        };
-        result.push(self.table_to_query());
-        match self.set_to_query() {
-            Some(value) => result.push(value),
-            None => (),
-        };
-        match self.from_items_to_query() {
-            Some(value) => result.push(value),
-            None => (),
-        };
-        match self.returning_fields_to_query() {
-            Some(value) => result.push(value),
-            None => (),
-        };
-        match self.where_to_query() {
-            Some(value) => result.push(value),
-            None => (),
-        };
-        result.join(" ")
-    }
-
-    fn get_ref_params(self) -> Vec<&'static (dyn ToSql + Sync)> {
+        let mut sections = Sections::new();
+        sections.push(Clause::With, self.with_queries_to_query());
+        sections.push(Clause::Target, self.table_to_query());
+        sections.push(Clause::Set, self.set_to_query());
+        sections.push(Clause::From, self.items_to_query());
+        sections.push(Clause::Where, self.where_to_query());
+        sections.push(Clause::Returning, self.returning_fields_to_query());
+        sections.render()
+    }
+
+    fn get_ref_params(&self) -> Vec<&(dyn ToSql + Sync)> {
         self.params.get_refs()
     }
+
+    fn into_params(self) -> Bucket {
+        self.params
+    }
+
+    fn validate(&self) -> Result<(), QueryBuildError> {
+        self.config.check_idents(&self.params)?;
+        self.params.check_subqueries()?;
+        if self.fields.is_empty() {
+            return Err(QueryBuildError::MissingSet);
+        }
+        if self.conditions.is_empty() && self.config.full_table == FullTablePolicy::Deny {
+            return Err(QueryBuildError::MissingWhere);
+        }
+        Ok(())
+    }
+}
+
+impl QueryBuilderWithFullTableGuard for UpdateBuilder {
+    fn full_table_policy(&mut self, policy: FullTablePolicy) -> &mut Self {
+        self.config.full_table = policy;
+        self
+    }
 }
 
 impl QueryBuilderWithWhere for UpdateBuilder {
@@ -144,37 +173,50 @@ impl QueryBuilderWithWhere for UpdateBuilder {
 }
 
 impl QueryBuilderWithSet for UpdateBuilder {
-    fn set<T: 'static + ToSql + Sync + Clone>(&mut self, field: &str, value: T) -> &mut Self {
+    fn set<T: 'static + ToSql + Sync + Clone>(
+        &mut self,
+        field: impl Into<Ident>,
+        value: T,
+    ) -> &mut Self {
+        let field = self.params.ident(field);
         let index = self.params.push(value);
         self.fields.push(format!("{} = ${}", field, index));
         self
     }
 
-    fn set_computed(&mut self, field: &str, value: &str) -> &mut Self {
+    fn set_computed(&mut self, field: impl Into<Ident>, value: &str) -> &mut Self {
+        let field = self.params.ident(field);
         self.fields.push(format!("{} = {}", field, value));
         self
     }
 }
 
 impl QueryBuilderWithQueries for UpdateBuilder {
-    fn with_query(&mut self, name: &str, query: &str) -> &mut Self {
-        self.with_queries.push((name.into(), query.into()));
+    fn with(&mut self, query: WithQuery) -> &mut Self {
+        let item = query.render(&mut self.params);
+        self.with_queries.push(item);
+        self
+    }
+
+    fn with_recursive(&mut self) -> &mut Self {
+        self.recursive = true;
         self
     }
 }
 
 impl QueryBuilderWithReturningColumns for UpdateBuilder {
-    fn returning(&mut self, fields: Vec<&str>) -> &mut Self {
+    fn returning<I: Into<Ident>>(&mut self, fields: Vec<I>) -> &mut Self {
         for field in fields {
-            self.returning_fields.push(field.to_string());
+            let field = self.params.ident(field);
+            self.returning_fields.push(field);
         }
         self
     }
 }
 
 impl QueryBuilderWithFrom for UpdateBuilder {
-    fn from(&mut self, item: &str) -> &mut Self {
-        self.from_items.push(item.into());
+    fn from_raw(&mut self, item: &str) -> &mut Self {
+        self.from_items.push(item.to_string());
         self
     }
 }
@@ -213,6 +255,56 @@ pub mod test {
         );
     }
 
+    #[test]
+    fn clause_combinations() {
+        for mask in 0..32 {
+            let enabled = |index: usize| mask & (1 << index) != 0;
+            let mut builder = UpdateBuilder::new("publishers");
+            let mut expected = vec![];
+            if enabled(0) {
+                builder.returning(vec!["id"]);
+            }
+            if enabled(1) {
+                builder.where_condition("publishers.id = tmp.id");
+            }
+            if enabled(2) {
+                builder.from("tmp");
+            }
+            if enabled(3) {
+                builder.set_computed("name", "tmp.name");
+            }
+            if enabled(4) {
+                builder.with_query("tmp", "SELECT 1 AS id, 'rick' AS name");
+                expected.push("WITH tmp AS (SELECT 1 AS id, 'rick' AS name)");
+            }
+            expected.push("UPDATE publishers");
+            if enabled(3) {
+                expected.push("SET name = tmp.name");
+            }
+            if enabled(2) {
+                expected.push("FROM tmp");
+            }
+            if enabled(1) {
+                expected.push("WHERE publishers.id = tmp.id");
+            }
+            if enabled(0) {
+                expected.push("RETURNING id");
+            }
+            assert_eq!(builder.get_query(), expected.join(" "));
+        }
+    }
+
+    #[test]
+    fn with_fields_and_where_expr() {
+        let mut builder = UpdateBuilder::new("publishers");
+        builder.set("id", 5);
+        builder.where_expr(!Condition::eq("trololo", 42).and(Condition::ne("tralala", 1)));
+        assert_eq!(
+            builder.get_query(),
+            "UPDATE publishers SET id = $1 WHERE NOT (trololo = $2 AND tralala <> $3)"
+        );
+    }
+
     #[test]
     fn with_set_from_items_and_where() {
         let mut qb = UpdateBuilder::new("features");
@@ -227,23 +319,38 @@ pub mod test {
         );
     }
 
+    #[test]
+    fn try_build() {
+        let mut builder = UpdateBuilder::new("users");
+        builder.where_eq("id", 42);
+        builder.returning(vec!["id"]);
+        assert_eq!(builder.try_build().err(), Some(QueryBuildError::MissingSet));
+        builder.set_computed("name", "upper(name)");
+        let built = builder.try_build().unwrap();
+        assert_eq!(
+            built.query,
+            "UPDATE users SET name = upper(name) WHERE id = $1 RETURNING id"
+        );
+        assert_eq!(built.params.len(), 1);
+    }
+
     #[test]
     fn with_set_from_items_where_and_subquery() {
         let mut subquery_builder = SelectBuilder::new("data_delivery_tiles");
-        let subquery = subquery_builder
+        subquery_builder
             .select("ST_Transform(ST_Union(data_delivery_tiles.geom), 4674) as geom")
             .where_eq("dataset_id", 0)
-            .group_by("dataset_id")
-            .get_query();
+            .group_by("dataset_id");
         let mut builder = UpdateBuilder::new("features");
         let query = builder
             .where_eq("features.id", 1)
             .set_computed("geom", "tiles.geom")
-            .from(format!("({}) tiles", subquery).as_str())
+            .from_subquery("tiles", subquery_builder)
             .get_query();
         assert_eq!(
             query.to_lowercase(),
-            "update features set geom = tiles.geom from (select st_transform(st_union(data_delivery_tiles.geom), 4674) as geom from data_delivery_tiles where dataset_id = $1 group by dataset_id) tiles where features.id = $1"
+            "update features set geom = tiles.geom from (select st_transform(st_union(data_delivery_tiles.geom), 4674) as geom from data_delivery_tiles where dataset_id = $2 group by dataset_id) tiles where features.id = $1"
         );
+        assert_eq!(builder.get_ref_params().len(), 2);
     }
 }
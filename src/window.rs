@@ -0,0 +1,258 @@
+use crate::error::QueryBuildError;
+use crate::ident::Ident;
+use crate::prelude::Order;
+use std::fmt;
+
+/// Unit used to measure the frame of a window
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameUnit {
+    Range,
+    Rows,
+    Groups,
+}
+
+impl fmt::Display for FrameUnit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrameUnit::Range => write!(f, "RANGE"),
+            FrameUnit::Rows => write!(f, "ROWS"),
+            FrameUnit::Groups => write!(f, "GROUPS"),
+        }
+    }
+}
+
+/// Start or end of the frame of a window, offsets are raw sql
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FrameBound {
+    UnboundedPreceding,
+    Preceding(String),
+    CurrentRow,
+    Following(String),
+    UnboundedFollowing,
+}
+
+impl fmt::Display for FrameBound {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrameBound::UnboundedPreceding => write!(f, "UNBOUNDED PRECEDING"),
+            FrameBound::Preceding(offset) => write!(f, "{} PRECEDING", offset),
+            FrameBound::CurrentRow => write!(f, "CURRENT ROW"),
+            FrameBound::Following(offset) => write!(f, "{} FOLLOWING", offset),
+            FrameBound::UnboundedFollowing => write!(f, "UNBOUNDED FOLLOWING"),
+        }
+    }
+}
+
+/// Rows removed from the frame of a window
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameExclusion {
+    CurrentRow,
+    Group,
+    Ties,
+    NoOthers,
+}
+
+impl fmt::Display for FrameExclusion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrameExclusion::CurrentRow => write!(f, "EXCLUDE CURRENT ROW"),
+            FrameExclusion::Group => write!(f, "EXCLUDE GROUP"),
+            FrameExclusion::Ties => write!(f, "EXCLUDE TIES"),
+            FrameExclusion::NoOthers => write!(f, "EXCLUDE NO OTHERS"),
+        }
+    }
+}
+
+/// Window definition, used by `SelectBuilder::select_over` and `SelectBuilder::window`
+///
+/// # Examples
+///
+/// ```
+/// use postgres_querybuilder::prelude::{FrameBound, FrameExclusion, FrameUnit, Order, Window};
+///
+/// let window = Window::new()
+///     .partition_by("user_id")
+///     .order_by(Order::Asc("created_at".into()))
+///     .frame_between(FrameUnit::Rows, FrameBound::Preceding("3".into()), FrameBound::CurrentRow)
+///     .exclude(FrameExclusion::Ties);
+///
+/// assert_eq!(window.to_string(), "PARTITION BY user_id ORDER BY created_at ASC ROWS BETWEEN 3 PRECEDING AND CURRENT ROW EXCLUDE TIES");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Window {
+    existing: Option<String>,
+    partitions: Vec<String>,
+    order: Vec<Order>,
+    frame: Option<(FrameUnit, FrameBound, Option<FrameBound>)>,
+    exclusion: Option<FrameExclusion>,
+}
+
+impl Window {
+    /// Create an empty window, spanning the whole result
+    pub fn new() -> Self {
+        Window::default()
+    }
+
+    /// Create a window based on a window declared with `SelectBuilder::window`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use postgres_querybuilder::prelude::{Order, Window};
+    ///
+    /// assert_eq!(Window::named("w").over(), "OVER w");
+    /// assert_eq!(Window::named("w").order_by(Order::Desc("total".into())).over(), "OVER (w ORDER BY total DESC)");
+    /// ```
+    pub fn named(name: impl Into<Ident>) -> Self {
+        Window {
+            existing: Some(name.into().to_string()),
+            ..Window::default()
+        }
+    }
+
+    /// Split the rows in partitions sharing the same value
+    ///
+    /// A window based on a named window can't change its partitions, `try_build`
+    /// fails with `QueryBuildError::WindowPartitionOverride` in that case. It can't
+    /// add an order to a named window that has one either, and a named window with
+    /// a frame can only be referenced as is.
+    pub fn partition_by(mut self, field: impl Into<Ident>) -> Self {
+        self.partitions.push(field.into().to_string());
+        self
+    }
+
+    /// Order the rows of each partition
+    pub fn order_by(mut self, order: Order) -> Self {
+        self.order.push(order);
+        self
+    }
+
+    /// Set the frame from a start to the current row
+    pub fn frame(mut self, unit: FrameUnit, start: FrameBound) -> Self {
+        self.frame = Some((unit, start, None));
+        self
+    }
+
+    /// Set the frame between two bounds
+    pub fn frame_between(mut self, unit: FrameUnit, start: FrameBound, end: FrameBound) -> Self {
+        self.frame = Some((unit, start, Some(end)));
+        self
+    }
+
+    /// Remove rows around the current row from the frame, only rendered once
+    /// the frame is set with `frame` or `frame_between`
+    pub fn exclude(mut self, exclusion: FrameExclusion) -> Self {
+        self.exclusion = Some(exclusion);
+        self
+    }
+
+    /// Render the `OVER` clause, referencing a named window directly when nothing is added to it
+    pub fn over(&self) -> String {
+        match self.existing.as_ref() {
+            Some(name) if self.is_reference() => format!("OVER {}", name),
+            _ => format!("OVER ({})", self),
+        }
+    }
+
+    /// Window name and partitions, checked by the identifier policy of the builder
+    pub(crate) fn idents(&self) -> impl Iterator<Item = &String> {
+        self.existing.iter().chain(self.partitions.iter())
+    }
+
+    /// Name of the named window this window is based on
+    pub(crate) fn base(&self) -> Option<&String> {
+        self.existing.as_ref()
+    }
+
+    /// Check the rules postgres applies when copying the named window `base`
+    /// into this window, `base` is `None` when it wasn't declared
+    pub(crate) fn check_copy(&self, base: Option<&Window>) -> Result<(), QueryBuildError> {
+        let name = match self.existing.as_ref() {
+            Some(name) => name.clone(),
+            None => return Ok(()),
+        };
+        if !self.partitions.is_empty() {
+            return Err(QueryBuildError::WindowPartitionOverride { name });
+        }
+        match base {
+            Some(base) if !base.order.is_empty() && !self.order.is_empty() => {
+                Err(QueryBuildError::WindowOrderOverride { name })
+            }
+            Some(base) if base.frame.is_some() => Err(QueryBuildError::WindowFrameCopy { name }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Whether the window only references a named window, rendered as `OVER name`
+    pub(crate) fn is_reference(&self) -> bool {
+        self.partitions.is_empty() && self.order.is_empty() && self.frame.is_none()
+    }
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut items = vec![];
+        if let Some(name) = self.existing.as_ref() {
+            items.push(name.clone());
+        }
+        if !self.partitions.is_empty() {
+            items.push(format!("PARTITION BY {}", self.partitions.join(", ")));
+        }
+        if !self.order.is_empty() {
+            let order: Vec<String> = self.order.iter().map(|order| order.to_string()).collect();
+            items.push(format!("ORDER BY {}", order.join(", ")));
+        }
+        match self.frame.as_ref() {
+            Some((unit, start, Some(end))) => {
+                items.push(format!("{} BETWEEN {} AND {}", unit, start, end))
+            }
+            Some((unit, start, None)) => items.push(format!("{} {}", unit, start)),
+            None => (),
+        }
+        match self.exclusion {
+            Some(exclusion) if self.frame.is_some() => items.push(exclusion.to_string()),
+            _ => (),
+        }
+        write!(f, "{}", items.join(" "))
+    }
+}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+
+    #[test]
+    fn render_over() {
+        assert_eq!(Window::new().over(), "OVER ()");
+        assert_eq!(
+            Window::new()
+                .partition_by("user_id")
+                .partition_by("kind")
+                .frame(FrameUnit::Groups, FrameBound::UnboundedPreceding)
+                .over(),
+            "OVER (PARTITION BY user_id, kind GROUPS UNBOUNDED PRECEDING)"
+        );
+        assert_eq!(
+            Window::named("w")
+                .frame_between(
+                    FrameUnit::Range,
+                    FrameBound::Preceding("'1 day'::interval".into()),
+                    FrameBound::UnboundedFollowing
+                )
+                .exclude(FrameExclusion::CurrentRow)
+                .over(),
+            "OVER (w RANGE BETWEEN '1 day'::interval PRECEDING AND UNBOUNDED FOLLOWING EXCLUDE CURRENT ROW)"
+        );
+        assert_eq!(
+            Window::named("w").exclude(FrameExclusion::Group).over(),
+            "OVER w"
+        );
+        assert_eq!(
+            Window::new()
+                .order_by(Order::Asc("created_at".into()))
+                .exclude(FrameExclusion::Ties)
+                .over(),
+            "OVER (ORDER BY created_at ASC)"
+        );
+    }
+}